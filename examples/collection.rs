@@ -0,0 +1,52 @@
+#[macro_use]
+extern crate argonaut;
+
+use std::collections::VecDeque;
+use std::env;
+use argonaut::{ArgDef, parse_plain};
+use std::process;
+
+/// Keeps the last few items given, in a `VecDeque` from the standard library.
+///
+/// Because of the orphan rules, `impl_collection_target!` can't be used on a
+/// collection from another crate directly (unless that crate does it itself,
+/// eg. behind an optional `argonaut` feature). Instead the collection is 
+/// wrapped in a local newtype with an adding method, and the macro is used on 
+/// that.
+#[derive(Debug)]
+struct Recent<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> Recent<T> {
+    fn with_capacity(capacity: usize) -> Recent<T> {
+        Recent { items: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    /// Adds an item, dropping the oldest one when the buffer is full.
+    fn push_overwrite(&mut self, item: T) {
+        if self.items.len() == self.capacity {
+            self.items.pop_front();
+        }
+        self.items.push_back(item);
+    }
+}
+
+impl_collection_target!(Recent, push_overwrite);
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    // Try 'collection --recent 1 --recent 2 --recent 3 --recent 4'.
+    let mut recent: Recent<u32> = Recent::with_capacity(3);
+    if let Err(e) = parse_plain("collection", &args, vec![
+        ArgDef::collect("recent", &mut recent).short("r")
+            .help("A number to remember (only the last three are kept)."),
+    ]) {
+        println!("Parse failed: {}", e);
+        process::exit(1);
+    }
+
+    println!("Recent: {:?}", recent.items);
+}
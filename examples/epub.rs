@@ -119,7 +119,7 @@ fn epub_main() -> Option<i32> {
     // Otherwise return an error code to be set with process::exit.
     {
         Ok(_) => None,
-        Err(ParseError::Interrupted(_)) => None,
+        Err(ParseError::Interrupted(..)) => None,
         Err(_) => Some(1),
     }
 }
@@ -0,0 +1,52 @@
+extern crate argonaut;
+
+use std::env;
+use argonaut::{ArgDef, parse_with, ParseConfig, ParseError};
+use std::process;
+
+fn main() {
+    if let Some(exit_code) = handles_main() {
+        process::exit(exit_code);
+    }
+}
+
+fn handles_main() -> Option<i32> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    
+    let mut verbose = false;
+    let mut quiet = false;
+    let mut output: Option<String> = None;
+    
+    // Registering a definition adds it to the list and returns a handle to it,
+    // so that the parse summary can be queried without repeating the names.
+    let mut defs = Vec::new();
+    let verbose_opt = ArgDef::flag("verbose", &mut verbose).short("v")
+        .help("Print as much information as possible.")
+        .register(&mut defs);
+    let quiet_opt = ArgDef::flag("quiet", &mut quiet).short("q")
+        .help("Print nothing at all.")
+        .register(&mut defs);
+    let output_opt = ArgDef::setting("output", &mut output).short("o")
+        .help("Where to write the result.")
+        .register(&mut defs);
+    defs.push(ArgDef::default_help("Shows how to use option handles.").short("h"));
+    
+    let summary = match parse_with("handles", &args, defs, ParseConfig::new()) {
+        Ok(summary) => summary,
+        Err(ParseError::Interrupted(..)) => return None,
+        Err(_) => return Some(1),
+    };
+    
+    // A typo in a handle name is a compile error, unlike a typo in a string.
+    if summary.was_given(verbose_opt) && summary.was_given(quiet_opt) {
+        println!("'--verbose' and '--quiet' cannot be used together.");
+        return Some(1);
+    }
+    
+    if summary.was_given(output_opt) {
+        println!("Writing to {:?}", output);
+    }
+    println!("Verbose: {}, quiet: {}", verbose, quiet);
+    
+    None
+}
@@ -1,7 +1,6 @@
 extern crate argonaut;
 
 use std::env;
-use std::iter;
 use argonaut::{ArgDef, parse, ParseError};
 use std::process;
 use std::collections::HashSet;
@@ -104,7 +103,7 @@ fn argonaut_main() -> Option<i32> {
         // description of the program.
         // 
         // When '--help' is passed, the parse is interrupted and 
-        // Err(Interrupted("help")) will be returned.
+        // Err(Interrupted("help", 0)) will be returned.
         // 
         // As this 'interrupts' the parsing, the required values,
         // 'one', 'two' and 'third-is-better', will not have been set.
@@ -113,6 +112,11 @@ fn argonaut_main() -> Option<i32> {
         // Declare a default '--version' argument that prints the SemVer version
         // from 'Cargo.toml' and interrupts.
         ArgDef::default_version(),
+        
+        // Declare a '--license' argument that prints the returned text and
+        // interrupts, just like '--version'.
+        ArgDef::report("license", || String::from("This program is licensed under MIT or Apache-2.0."))
+            .help("Print the license and abort."),
     ]) {
         // If the parse has succeeded, all required (positional) values will
         // have been assigned to their target variables.
@@ -123,7 +127,7 @@ fn argonaut_main() -> Option<i32> {
         
         // If the parse receives an 'interrupt' flag, the required values will
         // not have been set, and the program should exit.
-        Err(ParseError::Interrupted(_)) => {
+        Err(ParseError::Interrupted(..)) => {
             return None;
         },
         
@@ -141,7 +145,7 @@ fn argonaut_main() -> Option<i32> {
     println!("First:   {}", first);
     println!("Second:  {}", second);
     println!("Third is better?: {}", third);
-    println!("");
+    println!();
     println!("Numbers:          {:?}", numbers);
     println!("Included files:   {:?}", includes);
     if verbose {
@@ -152,7 +156,7 @@ fn argonaut_main() -> Option<i32> {
     } else {
         println!("Nothing's cool anymore");
     }
-    println!("Library rating: {}", iter::repeat('*').take(stars).collect::<String>());
+    println!("Library rating: {}", "*".repeat(stars));
     
     None
 }
@@ -0,0 +1,45 @@
+extern crate argonaut;
+
+use std::env;
+use argonaut::{ArgDef, parse_partial, parse_plain, ParseConfig};
+use std::process;
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    
+    // Try 'partial -v build --release --target wasm'.
+    // The front-end only knows '--verbose' and the command name, and leaves
+    // the rest for the parser of the command.
+    let mut verbose = false;
+    let mut command = String::new();
+    let rest = match parse_partial("partial", &args, vec![
+        ArgDef::flag("verbose", &mut verbose).short("v")
+            .help("Print as much information as possible."),
+        ArgDef::positional("command", &mut command)
+            .help("The command to run."),
+    ], ParseConfig::new()) {
+        Ok((_, rest)) => rest,
+        Err(e) => {
+            println!("Parse failed: {}", e);
+            process::exit(1);
+        }
+    };
+    
+    let mut release = false;
+    let mut target: Option<String> = None;
+    let program = format!("partial {}", command);
+    if let Err(e) = parse_plain(program, &rest, vec![
+        ArgDef::flag("release", &mut release)
+            .help("Build with optimizations."),
+        ArgDef::setting("target", &mut target)
+            .help("The platform to build for."),
+    ]) {
+        println!("Parse failed: {}", e);
+        process::exit(1);
+    }
+    
+    println!("Verbose: {}", verbose);
+    println!("Command: {}", command);
+    println!("Release: {}", release);
+    println!("Target:  {:?}", target);
+}
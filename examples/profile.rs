@@ -0,0 +1,54 @@
+extern crate argonaut;
+
+use std::env;
+use std::collections::HashMap;
+use argonaut::{ArgDef, parse_two_pass, ParseConfig, ParseError};
+use std::process;
+
+fn main() {
+    if let Some(exit_code) = profile_main() {
+        process::exit(exit_code);
+    }
+}
+
+/// Looks up the defaults of a named profile.
+fn load_profile(name: &str) -> Result<HashMap<String, String>, String> {
+    let values: &[(&str, &str)] = match name {
+        "fast" => &[("jobs", "8"), ("optimize", "false")],
+        "release" => &[("jobs", "2"), ("optimize", "true")],
+        _ => return Err(format!("Unknown profile '{}' (expected fast or release)", name)),
+    };
+    Ok(values.iter().map(|&(k, v)| (k.to_string(), v.to_string())).collect())
+}
+
+fn profile_main() -> Option<i32> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    
+    let mut profile: Option<String> = None;
+    let mut jobs: Option<u32> = None;
+    let mut optimize = false;
+    
+    // Try 'profile --profile release --jobs 4'.
+    // The profile is selected by one option, and provides the defaults of the
+    // others, so the arguments are parsed again once it is known.
+    let summary = match parse_two_pass("profile", &args, vec![
+        ArgDef::setting("profile", &mut profile).param("name")
+            .help("The profile to take defaults from (fast or release)."),
+        ArgDef::setting("jobs", &mut jobs).short("j")
+            .help("The number of jobs to run at once."),
+        ArgDef::flag("optimize", &mut optimize).short("O")
+            .help("Optimize the build."),
+        ArgDef::default_help("Shows how to load defaults from a named profile.").short("h"),
+    ], "profile", load_profile, ParseConfig::new()) {
+        Ok(summary) => summary,
+        Err(ParseError::Interrupted(..)) => return None,
+        Err(_) => return Some(1),
+    };
+    
+    let source = |name| if summary.was_given(name) { "arguments" } else { "profile" };
+    println!("Jobs:     {:?} (from the {})", jobs, source("jobs"));
+    println!("Optimize: {} (from the {})", optimize, source("optimize"));
+    println!("Given:    {}", summary.given_options().join(", "));
+    
+    None
+}
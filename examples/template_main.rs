@@ -1,7 +1,6 @@
 extern crate argonaut;
 
 use std::env;
-use std::iter;
 use argonaut::{ArgDef, parse, ParseError};
 use std::process;
 use std::collections::HashSet;
@@ -64,7 +63,7 @@ fn argonaut_main() -> Option<i32> {
         , ArgDef::default_version()
     ]) {
         Ok(_optional_error_code) => {},
-        Err(ParseError::Interrupted(_)) => {
+        Err(ParseError::Interrupted(..)) => {
             return None;
         },
         Err(_) => {
@@ -76,7 +75,7 @@ fn argonaut_main() -> Option<i32> {
     println!("First:   {}", first);
     println!("Second:  {}", second);
     println!("Third is better?: {}", third);
-    println!("");
+    println!();
     println!("Numbers:          {:?}", numbers);
     println!("Included files:   {:?}", includes);
     if verbose {
@@ -87,7 +86,7 @@ fn argonaut_main() -> Option<i32> {
     } else {
         println!("Nothing's cool anymore");
     }
-    println!("Library rating: {}", iter::repeat('*').take(stars).collect::<String>());
+    println!("Library rating: {}", "*".repeat(stars));
     
     // Return no error code
     None
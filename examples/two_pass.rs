@@ -0,0 +1,57 @@
+extern crate argonaut;
+
+use std::env;
+use std::collections::HashMap;
+use argonaut::{ArgDef, parse_two_pass, ParseConfig, ParseError};
+use std::process;
+
+fn main() {
+    if let Some(exit_code) = two_pass_main() {
+        process::exit(exit_code);
+    }
+}
+
+/// A stand-in for reading and parsing a real configuration file.
+fn load_config(path: &str) -> Result<HashMap<String, String>, String> {
+    if path != "server.conf" {
+        return Err(format!("Could not read config file '{}'", path));
+    }
+    let mut defaults = HashMap::new();
+    defaults.insert(String::from("host"), String::from("example.com"));
+    defaults.insert(String::from("port"), String::from("8080"));
+    defaults.insert(String::from("verbose"), String::from("true"));
+    Ok(defaults)
+}
+
+fn two_pass_main() -> Option<i32> {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    
+    let mut config: Option<String> = None;
+    let mut host: Option<String> = None;
+    let mut port: Option<u16> = None;
+    let mut verbose = false;
+    
+    // Try 'two_pass --config server.conf --port 9000'.
+    // The port given in the arguments wins over the one in the file.
+    match parse_two_pass("two_pass", &args, vec![
+        ArgDef::setting("config", &mut config).param("file")
+            .help("A file with default values for the other options."),
+        ArgDef::setting("host", &mut host)
+            .help("The host to connect to."),
+        ArgDef::setting("port", &mut port)
+            .help("The port to connect to."),
+        ArgDef::flag("verbose", &mut verbose).short("v")
+            .help("Print as much information as possible."),
+        ArgDef::default_help("Shows how to load defaults from a config file.").short("h"),
+    ], "config", load_config, ParseConfig::new()) {
+        Ok(_) => {}
+        Err(ParseError::Interrupted(..)) => return None,
+        Err(_) => return Some(1),
+    }
+    
+    println!("Host:    {:?}", host);
+    println!("Port:    {:?}", port);
+    println!("Verbose: {}", verbose);
+    
+    None
+}
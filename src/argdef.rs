@@ -1,20 +1,135 @@
 use std::str::FromStr;
 use std::fmt::Debug;
-use std::borrow::Cow;
+use std::borrow::{Borrow, Cow};
 use std::rc::Rc;
 use help::Help;
 use parse::ParseError;
-use std::collections::{BinaryHeap, BTreeSet, HashSet, LinkedList, VecDeque};
+use std::collections::{BinaryHeap, BTreeMap, BTreeSet, HashMap, HashSet, LinkedList, VecDeque};
 use std::hash::Hash;
+use std::io::{self, Write};
+use std::mem;
 
 pub type SubCmd<'def> = Box<FnMut(String, &[&str]) -> Result<Option<i32>, ParseError<'def>>>;
 
+/// The callback of an interrupt, which also gets the spelling that triggered 
+/// it and the arguments after it.
+pub(crate) type InterruptCallback<'def> = Box<FnMut(Rc<Help<'def>>, &str, &[&str]) -> InterruptFlow>;
+
+/// The sign that the numeric value of an argument must have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Sign {
+    /// Greater than zero.
+    Positive,
+    /// Zero or greater.
+    NonNegative,
+}
+
+impl Sign {
+    /// Checks the value of the argument, if it is a number.
+    pub(crate) fn check(&self, name: &str, value: &str) -> Result<(), String> {
+        let number = match value.parse::<f64>() {
+            Ok(number) if ! number.is_nan() => number,
+            _ => return Ok(()),
+        };
+        match *self {
+            Sign::Positive if number <= 0.0 => {
+                Err(format!("Argument '{}' must be positive, not '{}'", name, value))
+            }
+            Sign::NonNegative if number < 0.0 => {
+                Err(format!("Argument '{}' must not be negative, not '{}'", name, value))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The callback that is run when a count reaches its threshold.
+pub(crate) type CountCallback<'def> = Box<FnMut() + 'def>;
+
+/// The callback that computes the default value of a setting.
+pub(crate) type DefaultCallback<'def> = Box<FnMut(&DefaultContext) -> String + 'def>;
+
+/// The values known when computed defaults are evaluated 
+/// (see `ArgDef::default_with`).
+#[derive(Debug, Clone, Default)]
+pub struct DefaultContext {
+    values: HashMap<String, String>,
+}
+
+impl DefaultContext {
+    /// Returns the value of the positional argument or setting with the 
+    /// given name, as it was given (or defaulted), before it was parsed.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|value| value.as_str())
+    }
+    
+    pub(crate) fn insert<N: Into<String>, V: Into<String>>(&mut self, name: N, value: V) {
+        self.values.insert(name.into(), value.into());
+    }
+}
+
+/// What the parse should do after an interrupt callback has run.
+///
+/// Most interrupts, like `--help` in a command-line program, should `Abort`:
+/// the parse then fails with `ParseError::Interrupted` and the interrupt's 
+/// exit code, and the program is expected to exit. Interrupts that only 
+/// report something, like a `help` command in an interactive prompt, can
+/// `Continue` instead, so the rest of the arguments is parsed as if the 
+/// interrupt was a flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterruptFlow {
+    /// The interrupt was handled, and the parse goes on.
+    Continue,
+    /// The parse is interrupted with the exit code of the interrupt.
+    Abort,
+}
+
 /// The description of an expected argument.
+///
+/// Each definition borrows its target mutably until the parse is done, so
+/// the borrow checker rejects binding two definitions to the same variable:
+///
+/// ```compile_fail
+/// # use argonaut::{ArgDef, parse_plain};
+/// let mut verbose = false;
+/// parse_plain("prog", &["-v"], vec![
+///     ArgDef::flag("verbose", &mut verbose).short("v"),
+///     ArgDef::flag("loud", &mut verbose),
+/// ]).unwrap();
+/// ```
+///
+/// Definitions that share a target anyway (through unsafe code) are warned
+/// about when parsing in a debug build.
 //#[derive(Debug)]
 pub struct ArgDef<'def, 'tar> {
     pub(crate) name: Cow<'def, str>,
     pub(crate) kind: ArgDefKind<'def, 'tar>,
     pub(crate) help_desc: Option<Cow<'def, str>>,
+    pub(crate) help_long: Option<Cow<'def, str>>,
+    pub(crate) value_type: Option<Cow<'def, str>>,
+    pub(crate) hidden: bool,
+    pub(crate) required: bool,
+    pub(crate) long_short: bool,
+    pub(crate) non_empty: bool,
+    pub(crate) trim: bool,
+    pub(crate) list: bool,
+    pub(crate) sign: Option<Sign>,
+    pub(crate) unique: bool,
+    pub(crate) templated: bool,
+    pub(crate) eager: bool,
+    pub(crate) without_subcommand: bool,
+    pub(crate) scopes: Vec<Cow<'def, str>>,
+    pub(crate) warnings: Vec<String>,
+    pub(crate) requires: Vec<Cow<'def, str>>,
+    pub(crate) conflicts: Vec<Cow<'def, str>>,
+    pub(crate) together: Vec<Cow<'def, str>>,
+    pub(crate) implies: Vec<Cow<'def, str>>,
+    pub(crate) choices: Vec<Cow<'def, str>>,
+    pub(crate) choices_ignore_case: bool,
+    pub(crate) default_with: Option<DefaultCallback<'def>>,
+    pub(crate) at_count: Option<(usize, CountCallback<'def>)>,
+    pub(crate) env: Option<Cow<'def, str>>,
+    pub(crate) default: Option<Cow<'def, str>>,
 }
 
 //#[derive(Debug)]
@@ -22,12 +137,19 @@ pub(crate) enum ArgDefKind<'def, 'tar> {
     Positional { 
         target: &'tar mut SingleTarget,
     },
+    Command {
+        target: &'tar mut SingleTarget,
+        rest_name: Cow<'def, str>,
+        rest: &'tar mut Vec<String>,
+    },
     Subcommand {
         handler: SubCmd<'def>,
     },
     Trail { 
         target: &'tar mut CollectionTarget,
         optional: bool, 
+        delimiter: Option<char>,
+        bounds: Option<(usize, Option<usize>)>,
     },
     Flag {
         target: &'tar mut bool,
@@ -37,19 +159,34 @@ pub(crate) enum ArgDefKind<'def, 'tar> {
         target: &'tar mut usize,
         short: Option<Cow<'def, str>>,
     },
+    Features {
+        target: &'tar mut HashMap<String, bool>,
+        short: Option<Cow<'def, str>>,
+        param: Option<Cow<'def, str>>,
+    },
     Collect {
         target: &'tar mut CollectionTarget,
         short: Option<Cow<'def, str>>,
         param: Option<Cow<'def, str>>,
+        variadic: bool,
+        delimiter: Option<char>,
+        counter: Option<&'tar mut usize>,
+        key_separator: Option<char>,
+        terminator: Option<(Cow<'def, str>, bool)>,
     },
     Setting {
         target: &'tar mut OptionTarget,
         short: Option<Cow<'def, str>>,
         param: Option<Cow<'def, str>>,
+        greedy: bool,
+        overridable: bool,
+        presence: Option<&'tar mut bool>,
+        file_indirect: bool,
     },
     Interrupt {
-        callback: Box<FnMut(Rc<Help<'def>>)>,
+        callback: InterruptCallback<'def>,
         short: Option<Cow<'def, str>>,
+        exit_code: i32,
     },
 }
 
@@ -60,8 +197,33 @@ impl<'def, 'tar> ArgDef<'def, 'tar> {
     {
         ArgDef {
             name: name.into(),
-            kind: kind,
+            kind,
             help_desc: None,
+            help_long: None,
+            value_type: None,
+            hidden: false,
+            required: false,
+            long_short: false,
+            non_empty: false,
+            trim: false,
+            list: false,
+            sign: None,
+            unique: false,
+            templated: false,
+            eager: false,
+            without_subcommand: false,
+            scopes: Vec::new(),
+            warnings: Vec::new(),
+            requires: Vec::new(),
+            conflicts: Vec::new(),
+            together: Vec::new(),
+            implies: Vec::new(),
+            choices: Vec::new(),
+            choices_ignore_case: false,
+            default_with: None,
+            at_count: None,
+            env: None,
+            default: None,
         }
     }
     
@@ -75,6 +237,71 @@ impl<'def, 'tar> ArgDef<'def, 'tar> {
         ArgDef::new(name, ArgDefKind::Positional { target })
     }
     
+    /// Defines a required positional argument that takes one of the names of
+    /// a `Choice`, and sets its variable to the value paired with the name.
+    ///
+    /// This reads values into types that don't implement `FromStr`, like a
+    /// plain enum. A name that isn't listed fails the parse, with the names 
+    /// listed, and the names are shown as the value type in help messages 
+    /// (like `mode (fast|slow)`).
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, Choice, parse_plain};
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// enum Mode { Fast, Slow }
+    ///
+    /// let mut mode = Mode::Slow;
+    /// {
+    ///     let mut choice = Choice::new(&mut mode, &[("fast", Mode::Fast), ("slow", Mode::Slow)]);
+    ///     let result = parse_plain("prog", &["medium"], vec![
+    ///         ArgDef::pos_enum("mode", &mut choice),
+    ///     ]);
+    ///     assert_eq!("Invalid value 'medium' (expected one of: fast, slow)", 
+    ///                result.unwrap_err().to_string());
+    ///
+    ///     parse_plain("prog", &["fast"], vec![
+    ///         ArgDef::pos_enum("mode", &mut choice),
+    ///     ]).unwrap();
+    /// }
+    /// assert_eq!(Mode::Fast, mode);
+    /// ```
+    pub fn pos_enum<N, T>(name: N, target: &'tar mut Choice<T>) -> ArgDef<'def, 'tar> 
+      where N: Into<Cow<'def, str>>, T: Debug + Clone
+    {
+        let value_type = target.names().join("|");
+        ArgDef::positional(name, target).value_type(value_type)
+    }
+    
+    /// Defines a positional 'command' argument, which passes every argument
+    /// after it on as it is, like `tool build --release` for a `build` 
+    /// command that isn't parsed by this program.
+    ///
+    /// The command is the last positional argument, and is required. Options
+    /// before it are parsed as usual, while everything after it (options, 
+    /// `--` and all) is added to `rest` without being read. This means there
+    /// can be no trail, and options for the program must come before the 
+    /// command. Use a `--` before the command if it may start with a dash.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let (mut verbose, mut command, mut rest) = (false, String::new(), Vec::new());
+    /// parse_plain("tool", &["-v", "build", "--release", "-v"], vec![
+    ///     ArgDef::flag("verbose", &mut verbose).short("v"),
+    ///     ArgDef::command_and_rest("command", &mut command, "args", &mut rest),
+    /// ]).unwrap();
+    /// assert!(verbose);
+    /// assert_eq!("build", command);
+    /// assert_eq!(rest, vec!["--release", "-v"]);
+    /// ```
+    pub fn command_and_rest<N, R>(name: N, target: &'tar mut SingleTarget, rest_name: R, 
+            rest: &'tar mut Vec<String>) -> ArgDef<'def, 'tar> 
+      where N: Into<Cow<'def, str>>, R: Into<Cow<'def, str>>
+    {
+        ArgDef::new(name, ArgDefKind::Command { target, rest_name: rest_name.into(), rest })
+    }
+    
     /// Defines a 'trail'-type argument.
     ///
     /// The trail is a collection of the remaining positional arguments, after
@@ -83,7 +310,7 @@ impl<'def, 'tar> ArgDef<'def, 'tar> {
     pub fn trail<N>(name: N, optional: bool, target: &'tar mut CollectionTarget) -> ArgDef<'def, 'tar>
       where N: Into<Cow<'def, str>>
     {
-        ArgDef::new(name, ArgDefKind::Trail { optional, target })
+        ArgDef::new(name, ArgDefKind::Trail { optional, delimiter: None, bounds: None, target })
     }
     
     /// Defines a subcommand.
@@ -102,11 +329,59 @@ impl<'def, 'tar> ArgDef<'def, 'tar> {
     /// When the identifier for this argument is passed, the callback is run,
     /// and the parsing is interrupted. This is for options that should interrupt
     /// the parse when encountered, such as `--help` and `--version`.
-    pub fn interrupt<N, F>(name: N, callback: F) -> ArgDef<'def, 'tar>
+    ///
+    /// The interrupt has the exit code 0, unless another is set with 
+    /// `exit_code`.
+    ///
+    /// If the callback panics, the panic unwinds through the parse, unless
+    /// `ParseConfig::catch_interrupt_panics` is enabled.
+    pub fn interrupt<N, F>(name: N, mut callback: F) -> ArgDef<'def, 'tar>
       where N: Into<Cow<'def, str>>, F: FnMut(Rc<Help<'def>>) + 'static
+    {
+        ArgDef::spelled_interrupt(name, move |help, _| callback(help))
+    }
+    
+    /// Like `interrupt`, but the callback also gets the option as it was
+    /// given, like `-h` or `--help`.
+    pub fn spelled_interrupt<N, F>(name: N, mut callback: F) -> ArgDef<'def, 'tar>
+      where N: Into<Cow<'def, str>>, F: FnMut(Rc<Help<'def>>, &str) + 'static
+    {
+        ArgDef::interrupt_with_rest(name, move |help, spelling, _| {
+            callback(help, spelling);
+            InterruptFlow::Abort
+        })
+    }
+    
+    /// Like `spelled_interrupt`, but the callback decides whether the parse
+    /// is interrupted (see `InterruptFlow`).
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, InterruptFlow, parse_plain};
+    /// // A 'help' that doesn't end a command read by an interactive prompt.
+    /// let mut verbose = false;
+    /// parse_plain("cmd", &["--help", "-v"], vec![
+    ///     ArgDef::controlled_interrupt("help", |help, _| {
+    ///         help.print_help("Runs a command.");
+    ///         InterruptFlow::Continue
+    ///     }),
+    ///     ArgDef::flag("verbose", &mut verbose).short("v"),
+    /// ]).unwrap();
+    /// assert!(verbose);
+    /// ```
+    pub fn controlled_interrupt<N, F>(name: N, mut callback: F) -> ArgDef<'def, 'tar>
+      where N: Into<Cow<'def, str>>, F: FnMut(Rc<Help<'def>>, &str) -> InterruptFlow + 'static
+    {
+        ArgDef::interrupt_with_rest(name, move |help, spelling, _| callback(help, spelling))
+    }
+    
+    /// Like `spelled_interrupt`, but the callback also gets the arguments 
+    /// after the interrupt, which aren't parsed.
+    fn interrupt_with_rest<N, F>(name: N, callback: F) -> ArgDef<'def, 'tar>
+      where N: Into<Cow<'def, str>>, F: FnMut(Rc<Help<'def>>, &str, &[&str]) -> InterruptFlow + 'static
     {
         ArgDef::new(name, ArgDefKind::Interrupt { 
-            short: None, callback: Box::new(callback)
+            short: None, callback: Box::new(callback), exit_code: 0,
         })
     }
     
@@ -115,10 +390,63 @@ impl<'def, 'tar> ArgDef<'def, 'tar> {
     /// A optional setting that can only be set once.
     /// 
     /// The target should be an Option<T> where T: FromStr + Debug.
+    ///
+    /// The argument after the setting is always taken as its value, even if
+    /// it starts with a dash (`--opt --` sets `opt` to `"--"`). The value can
+    /// also be attached to the long name, like `--opt=value`, and may then be
+    /// empty (`--opt=`).
+    ///
+    /// A value that can't be parsed fails the parse, with the name of the 
+    /// type it was parsed into.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// # use std::net::{IpAddr, SocketAddr};
+    /// let (mut bind, mut peer) = (None::<SocketAddr>, None::<IpAddr>);
+    /// parse_plain("prog", &["--bind", "127.0.0.1:8080", "--peer", "::1"], vec![
+    ///     ArgDef::setting("bind", &mut bind),
+    ///     ArgDef::setting("peer", &mut peer),
+    /// ]).unwrap();
+    /// assert_eq!(Some(8080), bind.map(|bind| bind.port()));
+    ///
+    /// let result = parse_plain("prog", &["--bind", "127.0.0.1:http"], vec![
+    ///     ArgDef::setting("bind", &mut bind),
+    /// ]);
+    /// assert_eq!("Could not parse and convert '127.0.0.1:http' into SocketAddr", 
+    ///            result.unwrap_err().to_string());
+    ///
+    /// let result = parse_plain("prog", &["--peer", "300.0.0.1"], vec![
+    ///     ArgDef::setting("peer", &mut peer),
+    /// ]);
+    /// assert_eq!("Could not parse and convert '300.0.0.1' into IpAddr", 
+    ///            result.unwrap_err().to_string());
+    ///
+    /// let (mut opt, mut file) = (None::<String>, String::new());
+    /// parse_plain("prog", &["--opt", "--", "-f"], vec![
+    ///     ArgDef::setting("opt", &mut opt),
+    ///     ArgDef::positional("file", &mut file),
+    /// ]).unwrap_err();
+    ///
+    /// let (mut opt, mut file) = (None::<String>, String::new());
+    /// parse_plain("prog", &["--opt", "--", "--", "-f"], vec![
+    ///     ArgDef::setting("opt", &mut opt),
+    ///     ArgDef::positional("file", &mut file),
+    /// ]).unwrap();
+    /// assert_eq!(Some("--".to_string()), opt);
+    /// assert_eq!("-f", file);
+    ///
+    /// let (mut opt, mut flag) = (None::<String>, false);
+    /// parse_plain("prog", &["--opt="], vec![ArgDef::setting("opt", &mut opt)]).unwrap();
+    /// assert_eq!(Some(String::new()), opt);
+    /// let result = parse_plain("prog", &["--flag=1"], vec![ArgDef::flag("flag", &mut flag)]);
+    /// assert_eq!("Option '--flag' does not take a value", result.unwrap_err().to_string());
+    /// ```
     pub fn setting<N>(name: N, target: &'tar mut OptionTarget) -> ArgDef<'def, 'tar>
       where N: Into<Cow<'def, str>>
     {
-        ArgDef::new(name, ArgDefKind::Setting { short: None, param: None, target })
+        ArgDef::new(name, ArgDefKind::Setting { short: None, param: None, greedy: false, overridable: false, 
+            presence: None, file_indirect: false, target })
     }
     
     /// Defines a 'flag'-type argument.
@@ -130,6 +458,83 @@ impl<'def, 'tar> ArgDef<'def, 'tar> {
         ArgDef::new(name, ArgDefKind::Flag { short: None, target })
     }
     
+    /// Reads this flag from the environment variable when it isn't given in
+    /// the arguments, so the arguments win over the environment (and the 
+    /// environment over the defaults of the config).
+    ///
+    /// The value is matched regardless of case: `1`, `true`, `yes` and `on`
+    /// set the flag, while `0`, `false`, `no`, `off` and the empty string
+    /// clear it, which turns off a flag that starts out as true. This is the
+    /// same with `+` toggles, where the value decides the state of the flag, 
+    /// not which form it is given in. A variable that isn't set leaves the 
+    /// flag alone, and any other value fails the parse. The variable is 
+    /// listed in the "Environment" section of the help.
+    ///
+    /// The arguments also win when they turn the flag off: with `+` toggles
+    /// (see `ParseConfig::plus_toggles`), the dash forms like `--name` clear
+    /// a flag that the variable sets. Flags have no `no-` negation (only features
+    /// do), so without toggles, a flag set by the variable can only be 
+    /// cleared by changing the variable.
+    ///
+    /// A set variable counts as giving the flag when it is `required`, 
+    /// whatever its value, like a default does.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, Help, ParseConfig, parse_plain, parse_plain_with};
+    /// # use std::env;
+    /// fn color(args: &[&str]) -> Result<bool, String> {
+    ///     let mut color = false;
+    ///     parse_plain("prog", args, vec![
+    ///         ArgDef::flag("color", &mut color).env("DOCS_FLAG_COLOR"),
+    ///     ]).map_err(|e| e.to_string())?;
+    ///     Ok(color)
+    /// }
+    /// env::remove_var("DOCS_FLAG_COLOR");
+    /// assert_eq!(Ok(false), color(&[]));
+    /// for truthy in &["1", "true", "Yes", "ON"] {
+    ///     env::set_var("DOCS_FLAG_COLOR", truthy);
+    ///     assert_eq!(Ok(true), color(&[]));
+    /// }
+    /// env::set_var("DOCS_FLAG_COLOR", "no");
+    /// assert_eq!(Ok(false), color(&[]));
+    /// assert_eq!(Ok(true), color(&["--color"]));
+    /// let mut enabled = false;
+    /// let defs = vec![ArgDef::flag("color", &mut enabled).env("DOCS_FLAG_COLOR")];
+    /// assert!(Help::new("prog".into(), &defs).help_message("").ends_with("\
+    /// Environment:
+    ///   DOCS_FLAG_COLOR (set)
+    ///       Used for '--color' when it isn't given.
+    /// "));
+    /// env::set_var("DOCS_FLAG_COLOR", "maybe");
+    /// assert_eq!(Err("Invalid value 'maybe' in 'DOCS_FLAG_COLOR' for '--color' (expected 1, true, yes, on, 0, false, no or off)".to_string()), 
+    ///            color(&[]));
+    ///
+    /// fn toggled(args: &[&str]) -> Result<bool, String> {
+    ///     let mut color = false;
+    ///     parse_plain_with("prog", args, vec![
+    ///         ArgDef::flag("color", &mut color).env("DOCS_FLAG_COLOR").required(),
+    ///     ], ParseConfig::new().plus_toggles(true)).map_err(|e| e.to_string())?;
+    ///     Ok(color)
+    /// }
+    /// env::set_var("DOCS_FLAG_COLOR", "1");
+    /// assert_eq!(Ok(true), toggled(&[]));
+    /// assert_eq!(Ok(false), toggled(&["--color"]));
+    /// assert_eq!(Err("Unknown option '--no-color'".to_string()), color(&["--no-color"]));
+    /// env::set_var("DOCS_FLAG_COLOR", "0");
+    /// assert_eq!(Ok(false), toggled(&[]));
+    /// assert_eq!(Ok(true), toggled(&["+color"]));
+    /// env::remove_var("DOCS_FLAG_COLOR");
+    /// assert_eq!(Err("Missing required option: --color".to_string()), toggled(&[]));
+    /// ```
+    pub fn env<V>(mut self, var: V) -> Self where V: Into<Cow<'def, str>> {
+        match self.kind {
+            ArgDefKind::Flag { .. } => self.env = Some(var.into()),
+            _ => self.warn("Only flags can be read from environment variables (ArgDef error)"),
+        }
+        self
+    }
+    
     /// Creates a description of a `count`-type argument.
     /// 
     /// This will count the number of times the flag was passed in the arguments.
@@ -139,6 +544,60 @@ impl<'def, 'tar> ArgDef<'def, 'tar> {
         ArgDef::new(name, ArgDefKind::Count { short: None, target })
     }
     
+    /// Runs the callback when this count reaches the threshold, like turning
+    /// on tracing at the fourth `-v`.
+    ///
+    /// The callback runs in the middle of the parse, right after the option
+    /// that reaches the threshold is read, so the arguments after it have not
+    /// been read yet, and the parse may still fail. It runs once, as the
+    /// count only grows, and only for options given in the arguments (not 
+    /// for defaults or in a dry run).
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let (mut verbosity, mut trace) = (0, false);
+    /// parse_plain("prog", &["-v", "-v", "-v", "-v", "-v"], vec![
+    ///     ArgDef::count("verbose", &mut verbosity).short("v").at_count(4, || {
+    ///         println!("Tracing is enabled.");
+    ///         trace = true;
+    ///     }),
+    /// ]).unwrap();
+    /// assert_eq!((5, true), (verbosity, trace));
+    /// ```
+    pub fn at_count<F>(mut self, threshold: usize, callback: F) -> Self where F: FnMut() + 'def {
+        match self.kind {
+            ArgDefKind::Count { .. } => self.at_count = Some((threshold, Box::new(callback))),
+            _ => self.warn("Only 'count' arguments can have thresholds (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Defines a 'features'-type argument, which toggles named features.
+    ///
+    /// `--feature X` (or `--feature=X`) enables the feature `X` in the map, 
+    /// and `--no-feature X` disables it, so the map holds the last state 
+    /// given for each feature. This is like defining a flag for each feature,
+    /// when the features aren't known up front or are too many for that.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// # use std::collections::HashMap;
+    /// let mut features = HashMap::new();
+    /// parse_plain("prog", &["--feature", "gzip", "--feature=tls", "--no-feature", "gzip"], vec![
+    ///     ArgDef::features("feature", &mut features),
+    /// ]).unwrap();
+    /// assert_eq!(Some(&false), features.get("gzip"));
+    /// assert_eq!(Some(&true), features.get("tls"));
+    /// assert_eq!(None, features.get("http2"));
+    /// ```
+    pub fn features<N>(name: N, target: &'tar mut HashMap<String, bool>) -> ArgDef<'def, 'tar>
+      where N: Into<Cow<'def, str>> 
+    {
+        ArgDef::new(name, ArgDefKind::Features { short: None, param: None, target })
+    }
+    
     /// Defines a 'collect'-type argument.
     /// 
     /// The flag can be given multiple times, and each argument to it will
@@ -148,47 +607,193 @@ impl<'def, 'tar> ArgDef<'def, 'tar> {
     pub fn collect<N>(name: N, target: &'tar mut CollectionTarget) -> ArgDef<'def, 'tar>
       where N: Into<Cow<'def, str>> 
     {
-        ArgDef::new(name, ArgDefKind::Collect { short: None, param: None, target })
+        ArgDef::new(name, ArgDefKind::Collect { short: None, param: None, variadic: false, 
+            delimiter: None, counter: None, key_separator: None, terminator: None, target })
     }
 
     /// Creates a default help interrupt for `--help`.
+    ///
+    /// Hidden options are only shown when `-v` or `--verbose` is given right
+    /// after it (`--help -v`), as arguments before the interrupt have already
+    /// been read as the program's own. These are spelled with the prefixes of
+    /// the parse (`//help /v` with `ParseConfig::prefixes("/", "//")`), and 
+    /// `--verbose` is matched regardless of case when option case is ignored.
     pub fn default_help<D: Into<Cow<'static, str>>>(description: D) -> ArgDef<'def, 'tar> {
+        let description = description.into();
+        ArgDef::interrupt_with_rest("help", move |help, _, rest| {
+            match rest.first() {
+                Some(arg) if help.is_verbose(arg) => help.print_advanced_help(description.as_ref()),
+                _ => help.print_help(description.as_ref()),
+            }
+            InterruptFlow::Abort
+        }).help("Print this message and abort.")
+    }
+
+    /// Like `default_help`, but shows long help messages through the user's
+    /// pager (see `Help::page_help`, also for how the height of the terminal
+    /// is found).
+    pub fn default_paged_help<D: Into<Cow<'static, str>>>(description: D) -> ArgDef<'def, 'tar> {
         let description = description.into();
         ArgDef::interrupt("help", move |help| {
-            help.print_help(description.as_ref());
+            help.page_help(description.as_ref());
         }).help("Print this message and abort.")
     }
 
+    /// Like `default_help`, but `-h` prints a compact help message (see 
+    /// `Help::compact_help`), while `--help` prints the full one.
+    ///
+    /// Any long spelling prints the full help, like `--HELP` when the case is
+    /// ignored, or `--he` with abbreviations. The prefixes of the parse are 
+    /// used to tell them apart (see `ParseConfig::prefixes`).
+    pub fn default_compact_help<D: Into<Cow<'static, str>>>(description: D) -> ArgDef<'def, 'tar> {
+        let description = description.into();
+        ArgDef::spelled_interrupt("help", move |help, spelling| {
+            if let Some((true, _)) = help.prefixes.split(spelling) {
+                help.print_help(description.as_ref());
+            } else {
+                print!("{}", help.compact_help());
+            }
+        }).short("h").help("Print this message and abort (briefly with -h).")
+    }
+
     /// Creates a default version interrupt for `--version`.
     pub fn default_version() -> ArgDef<'def, 'tar> {
-        ArgDef::interrupt("version", |_| {
-            println!("{}", option_env!("CARGO_PKG_VERSION").unwrap_or("0.0.0"));
+        ArgDef::report("version", || {
+            option_env!("CARGO_PKG_VERSION").unwrap_or("0.0.0").to_string()
         }).help("Print version string and abort.")
     }
+    
+    /// Like `default_version`, but prints the given version as a line of 
+    /// JSON, with the program name (see `Help::version_json`).
+    ///
+    /// The version is given by the caller, usually as 
+    /// `env!("CARGO_PKG_VERSION")`, so that it is the version of the program
+    /// and not of this library.
+    pub fn default_version_json<V: Into<Cow<'static, str>>>(version: V) -> ArgDef<'def, 'tar> {
+        ArgDef::default_version_json_to(version, io::stdout())
+    }
+    
+    /// Like `default_version_json`, but writes the JSON to the given writer
+    /// instead of stdout (see `Help::write_version_json`).
+    ///
+    /// # Example
+    /// ```
+    /// use argonaut::{ArgDef, ParseError, parse_plain};
+    /// use std::cell::RefCell;
+    /// use std::io::{self, Write};
+    /// use std::rc::Rc;
+    ///
+    /// struct Shared(Rc<RefCell<Vec<u8>>>);
+    ///
+    /// impl Write for Shared {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> { self.0.borrow_mut().write(buf) }
+    ///     fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    /// }
+    ///
+    /// let output = Rc::new(RefCell::new(Vec::new()));
+    /// match parse_plain("prog", &["--version"], vec![
+    ///     ArgDef::default_version_json_to("1.2.3", Shared(output.clone())),
+    /// ]) {
+    ///     Err(ParseError::Interrupted(name, 0, _)) => assert_eq!("version", name),
+    ///     _ => unreachable!(),
+    /// }
+    /// assert_eq!("{\"version\":\"1.2.3\",\"name\":\"prog\"}\n", 
+    ///            String::from_utf8(output.borrow().clone()).unwrap());
+    /// ```
+    pub fn default_version_json_to<V, W>(version: V, mut out: W) -> ArgDef<'def, 'tar>
+      where V: Into<Cow<'static, str>>, W: Write + 'static
+    {
+        let version = version.into();
+        ArgDef::interrupt("version", move |help| {
+            let _ = help.write_version_json(version.as_ref(), &mut out);
+        }).help("Print version information as JSON and abort.")
+    }
+    
+    /// Creates an interrupt that prints the text returned by the closure to
+    /// stdout, like `--version` does, for options like `--license` or 
+    /// `--build-info`.
+    ///
+    /// The closure is only called when the option is given. The interrupt has
+    /// the exit code 0, unless another is set with `exit_code`.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse, ParseError};
+    /// let result = parse("prog", &["--license"], vec![
+    ///     ArgDef::report("license", || format!("{} is licensed under MIT.", "prog"))
+    ///         .help("Print the license and abort."),
+    /// ]);
+    /// match result {
+    ///     Err(ParseError::Interrupted(name, code, _)) => assert_eq!(("license", 0), (name.as_ref(), code)),
+    ///     other => panic!("Unexpected result: {:?}", other),
+    /// }
+    /// ```
+    pub fn report<N, F>(name: N, mut report: F) -> ArgDef<'def, 'tar>
+      where N: Into<Cow<'def, str>>, F: FnMut() -> String + 'static
+    {
+        ArgDef::interrupt(name, move |_| println!("{}", report()))
+    }
 
+    /// Records a misuse of a builder, which is reported when the definition
+    /// is parsed (see `ParseConfig::warnings`).
+    fn warn(&mut self, message: &str) {
+        self.warnings.push(message.to_string());
+    }
+    
     /// Adds a short identifier for this option, like `-h` for `--help`.
     ///
     /// # Example
     /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
     /// let mut eat_ice_cream = false;
-    /// parse(&["-e"], &[
+    /// parse_plain("prog", &["-e"], vec![
     ///     ArgDef::flag("eat_ice_cream", &mut eat_ice_cream).short("e"),
     /// ]).unwrap();
     /// assert_eq!(true, eat_ice_cream);
     /// ```
     pub fn short<N>(mut self, short: N) -> Self where N: Into<Cow<'def, str>> {
         use self::ArgDefKind::*;
-        self.kind = match self.kind {
-            Positional { .. } | Trail { .. } | Subcommand { .. } => {
-                println!("WARNING: Positional, trail and subcommand arguments cannot have a short identifier (ArgDef error)");
-                return self;
+        match self.kind {
+            Positional { .. } | Command { .. } | Trail { .. } | Subcommand { .. } => {
+                self.warn("Positional, trail and subcommand arguments cannot have a short identifier (ArgDef error)");
             },
-            Flag { target, .. } => Flag { short: Some(short.into()), target },
-            Count { target, .. } => Count { short: Some(short.into()), target },
-            Setting { target, param, .. } => Setting { short: Some(short.into()), target, param },
-            Interrupt { callback, .. } => Interrupt { short: Some(short.into()), callback },
-            Collect { target, param, .. } => Collect { short: Some(short.into()), target, param },
-        };
+            Flag { short: ref mut s, .. }
+            | Count { short: ref mut s, .. }
+            | Features { short: ref mut s, .. }
+            | Setting { short: ref mut s, .. }
+            | Interrupt { short: ref mut s, .. }
+            | Collect { short: ref mut s, .. } => *s = Some(short.into()),
+        }
+        self
+    }
+    
+    /// Allows the short identifier of this option to be longer than a single
+    /// character.
+    ///
+    /// Such shorts are rejected by default, as they only match exactly: 
+    /// `.short("ab")` is given as `-ab`, which can't be mistaken for `-a -b`.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, ParseError, parse_plain};
+    /// let mut all = false;
+    /// match parse_plain("prog", &["-ab"], vec![ArgDef::flag("all", &mut all).short("ab")]) {
+    ///     Err(ParseError::InvalidDefinitions(_)) => {}
+    ///     other => panic!("Unexpected result: {:?}", other),
+    /// }
+    /// parse_plain("prog", &["-ab"], vec![ArgDef::flag("all", &mut all).short("ab").long_short()]).unwrap();
+    /// assert!(all);
+    ///
+    /// // A single character is fine, even if it takes several bytes.
+    /// let mut e = false;
+    /// parse_plain("prog", &["-é"], vec![ArgDef::flag("e", &mut e).short("é")]).unwrap();
+    /// assert!(e);
+    /// ```
+    pub fn long_short(mut self) -> Self {
+        if self.short_id().is_none() {
+            self.warn("'long_short' was used before a short identifier was set (ArgDef error)");
+        }
+        self.long_short = true;
         self
     }
     
@@ -197,134 +802,1361 @@ impl<'def, 'tar> ArgDef<'def, 'tar> {
     /// This is only used for help messages.
     pub fn param<N>(mut self, parameter_name: N) -> Self where N: Into<Cow<'def, str>> {
         use self::ArgDefKind::*;
-        self.kind = match self.kind {
-            Setting { target, short, .. } => {
-                Setting { target, short, param: Some(parameter_name.into()) }
-            }
-            Collect { target, short, .. } => {
-                Collect { target, short, param: Some(parameter_name.into()) }
+        match self.kind {
+            Setting { ref mut param, .. } | Collect { ref mut param, .. } 
+            | Features { ref mut param, .. } => {
+                *param = Some(parameter_name.into());
             }
             _ => {
-                println!("WARNING: Only 'option' and 'collect' arguments have a parameter name (ArgDef error)");
-                return self;
+                self.warn("Only 'option' and 'collect' arguments have a parameter name (ArgDef error)");
             }
-        };
+        }
         self
     }
     
-    /// Adds a help description for this argument.
-    /// 
-    /// This is only used for help messages.
-    pub fn help<N>(mut self, help: N) -> Self where N: Into<Cow<'def, str>> {
-        self.help_desc = Some(help.into());
+    /// Makes a setting take every following argument up to the next known
+    /// option (or `--`), joined by spaces, as its value.
+    ///
+    /// `git commit --message fix the bug --amend` => `"fix the bug"`
+    pub fn greedy(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Setting { ref mut greedy, .. } => *greedy = true,
+            _ => self.warn("Only 'setting' arguments can be greedy (ArgDef error)"),
+        }
         self
     }
-}
-
-/// Allows every type that is FromStr to be read from an argument.
-pub trait SingleTarget: Debug {
-    /// Parses the value and updates self with it.
-    fn parse(&mut self, value: &str) -> Result<(), String>;
-}
-
-impl<T> SingleTarget for T where T: Debug + FromStr {
-    // TODO: Better info here.
-    fn parse(&mut self, value: &str) -> Result<(), String> {
-        let value = match <T as FromStr>::from_str(value) {
-            Ok(val) => val,
-            Err(_) => return Err(format!("Could not parse and convert '{}'", value)),
-        };
-        *self = value;
-        Ok(())
-    }
-}
-
-/// Allows every type that is FromStr to be read from an argument.
-pub trait OptionTarget: Debug {
-    /// Parses the value and updates self with it.
-    fn parse(&mut self, value: &str) -> Result<(), String>;
-}
-
-impl<T> OptionTarget for Option<T> where T: Debug + FromStr {
-    // TODO: Better info here.
-    fn parse(&mut self, value: &str) -> Result<(), String> {
-        let value = match <T as FromStr>::from_str(value) {
-            Ok(val) => val,
-            Err(_) => return Err(format!("Could not parse and convert '{}'", value)),
-        };
-        *self = Some(value);
-        Ok(())
-    }
-}
-
-/// Allows a collection to be extended with values read from arguments.
-pub trait CollectionTarget: Debug {
-    /// Parses the value and adds it to this collection.
-    fn parse_and_add(&mut self, value: &str) -> Result<(), String>;
-}
-
-impl<T> CollectionTarget for Vec<T> where T: Debug + FromStr {
-    fn parse_and_add(&mut self, value: &str) -> Result<(), String> {
-        let value = match <T as FromStr>::from_str(value) {
-            Ok(val) => val,
-            Err(_) => return Err(format!("Could not parse and convert '{}'", value)),
-        };
-        self.push(value);
-        Ok(())
+    
+    /// Lets a setting be given more than once, with the last value winning,
+    /// instead of failing the parse.
+    ///
+    /// The summary still reports the setting as given.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, ParseConfig, parse_plain_with};
+    /// let mut output: Option<String> = None;
+    /// let given = parse_plain_with("prog", &["--output", "a", "--output", "b"], vec![
+    ///     ArgDef::setting("output", &mut output).allow_override(),
+    /// ], ParseConfig::new()).unwrap().was_given("output");
+    /// assert!(given);
+    /// assert_eq!(Some("b".to_string()), output);
+    /// ```
+    pub fn allow_override(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Setting { ref mut overridable, .. } => *overridable = true,
+            _ => self.warn("Only 'setting' arguments can be overridden (ArgDef error)"),
+        }
+        self
     }
-}
-
-impl<T> CollectionTarget for BinaryHeap<T> where T: Debug + FromStr + Ord {
-    fn parse_and_add(&mut self, value: &str) -> Result<(), String> {
-        let value = match <T as FromStr>::from_str(value) {
-            Ok(val) => val,
-            Err(_) => return Err(format!("Could not parse and convert '{}'", value)),
-        };
-        self.push(value);
-        Ok(())
+    
+    /// Makes a setting also set the given flag to true when it is given.
+    ///
+    /// This tells whether the setting was passed even when its value equals
+    /// the one the target already had.
+    pub fn presence(mut self, flag: &'tar mut bool) -> Self {
+        match self.kind {
+            ArgDefKind::Setting { ref mut presence, .. } => *presence = Some(flag),
+            _ => self.warn("Only 'setting' arguments have a presence flag (ArgDef error)"),
+        }
+        self
     }
-}
-
-impl<T> CollectionTarget for BTreeSet<T> where T: Debug + FromStr + Ord {
-    fn parse_and_add(&mut self, value: &str) -> Result<(), String> {
-        let value = match <T as FromStr>::from_str(value) {
-            Ok(val) => val,
-            Err(_) => return Err(format!("Could not parse and convert '{}'", value)),
-        };
-        self.insert(value);
-        Ok(())
+    
+    /// Lets a setting read its value from a file, by giving it as `@path`.
+    /// The contents of the file are used as the value, without surrounding
+    /// whitespace.
+    ///
+    /// Values without a leading `@` are used as they are, and a literal `@`
+    /// can be given as `@@`, so `@@home` is read as `"@home"`. This is useful
+    /// for passing secrets without exposing them in the arguments.
+    pub fn file_indirect(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Setting { ref mut file_indirect, .. } => *file_indirect = true,
+            _ => self.warn("Only 'setting' arguments can be read from files (ArgDef error)"),
+        }
+        self
     }
-}
-
-impl<T> CollectionTarget for HashSet<T> where T: Debug + FromStr + Hash + Eq {
-    fn parse_and_add(&mut self, value: &str) -> Result<(), String> {
-        let value = match <T as FromStr>::from_str(value) {
-            Ok(val) => val,
-            Err(_) => return Err(format!("Could not parse and convert '{}'", value)),
-        };
-        self.insert(value);
-        Ok(())
+    
+    /// Sets the exit code of an interrupt, as returned by 
+    /// `ParseError::exit_code` when the parse is interrupted by it.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let defs = || vec![
+    ///     ArgDef::interrupt("doctor", |_| {}).exit_code(3),
+    ///     ArgDef::interrupt("panic", |_| {}).exit_code(4),
+    /// ];
+    /// assert_eq!(3, parse_plain("prog", &["--doctor"], defs()).unwrap_err().exit_code());
+    /// assert_eq!(4, parse_plain("prog", &["--panic"], defs()).unwrap_err().exit_code());
+    /// ```
+    pub fn exit_code(mut self, code: i32) -> Self {
+        match self.kind {
+            ArgDefKind::Interrupt { ref mut exit_code, .. } => *exit_code = code,
+            _ => self.warn("Only 'interrupt' arguments have an exit code (ArgDef error)"),
+        }
+        self
     }
-}
-
-impl<T> CollectionTarget for LinkedList<T> where T: Debug + FromStr {
-    fn parse_and_add(&mut self, value: &str) -> Result<(), String> {
-        let value = match <T as FromStr>::from_str(value) {
-            Ok(val) => val,
-            Err(_) => return Err(format!("Could not parse and convert '{}'", value)),
-        };
-        self.push_back(value);
-        Ok(())
+    
+    /// Makes a collector take every following argument up to the next one 
+    /// that starts with a dash (or `--`), instead of just one.
+    ///
+    /// `prog --files a b c --verbose` => `vec!["a", "b", "c"]`
+    ///
+    /// As this also takes the arguments that positional arguments would 
+    /// otherwise get, positionals should be given before the option, or after 
+    /// a `--`. A value attached with `=` is taken alone.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let (mut files, mut out, mut verbose) = (Vec::<String>::new(), String::new(), false);
+    /// parse_plain("prog", &["out.txt", "--files", "a", "b", "-v"], vec![
+    ///     ArgDef::positional("out", &mut out),
+    ///     ArgDef::collect("files", &mut files).variadic(),
+    ///     ArgDef::flag("verbose", &mut verbose).short("v"),
+    /// ]).unwrap();
+    /// assert_eq!(vec!["a", "b"], files);
+    /// assert_eq!("out.txt", out);
+    /// assert!(verbose);
+    ///
+    /// let (mut files, mut out) = (Vec::<String>::new(), String::new());
+    /// parse_plain("prog", &["--files", "a", "b", "--", "out.txt"], vec![
+    ///     ArgDef::positional("out", &mut out),
+    ///     ArgDef::collect("files", &mut files).variadic(),
+    /// ]).unwrap();
+    /// assert_eq!(vec!["a", "b"], files);
+    /// assert_eq!("out.txt", out);
+    /// ```
+    pub fn variadic(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Collect { ref mut variadic, .. } => *variadic = true,
+            _ => self.warn("Only 'collect' arguments can be variadic (ArgDef error)"),
+        }
+        self
     }
-}
+    
+    /// Makes the parse fail when the same value is given to this collector 
+    /// twice, instead of adding it again (or, for sets, dropping it).
+    ///
+    /// Values are compared as they are given, after splitting on the 
+    /// delimiter (if any), so `1` and `01` are different values.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let mut tags: Vec<String> = Vec::new();
+    /// parse_plain("prog", &["--tag", "a", "--tag", "b"], vec![
+    ///     ArgDef::collect("tag", &mut tags).unique(),
+    /// ]).unwrap();
+    /// assert_eq!(tags, vec!["a", "b"]);
+    ///
+    /// let result = parse_plain("prog", &["--tag", "a", "--tag", "a"], vec![
+    ///     ArgDef::collect("tag", &mut Vec::<String>::new()).unique(),
+    /// ]);
+    /// assert_eq!("Duplicate value 'a' for '--tag'", result.unwrap_err().to_string());
+    /// ```
+    pub fn unique(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Collect { .. } => self.unique = true,
+            _ => self.warn("Only 'collect' arguments can require unique values (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Makes a collector take every following argument up to a `;`, like
+    /// `find -exec` does (see `terminated_by`).
+    ///
+    /// `prog --exec rm -f {} ; --verbose` => `vec!["rm", "-f", "{}"]`
+    pub fn terminated(self) -> Self {
+        self.terminated_by(";", true)
+    }
+    
+    /// Makes a collector take every following argument up to the given 
+    /// terminator, which is not collected itself.
+    ///
+    /// The values are taken as they are, even when they start with a dash,
+    /// and there may be none (`--exec ;`). A value attached with `=` is taken
+    /// as the first one. When the arguments end before the terminator, the
+    /// parse fails if the terminator is `required`, and the values are kept
+    /// otherwise.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let mut command: Vec<String> = Vec::new();
+    /// let mut name: Option<String> = None;
+    /// parse_plain("find", &["-exec", "rm", "-f", "{}", ";", "-name", "*.o"], vec![
+    ///     ArgDef::collect("exec", &mut command).short("exec").long_short().terminated(),
+    ///     ArgDef::setting("name", &mut name).short("name").long_short(),
+    /// ]).unwrap();
+    /// assert_eq!(vec!["rm", "-f", "{}"], command);
+    /// assert_eq!(Some("*.o".to_string()), name);
+    /// ```
+    pub fn terminated_by<N>(mut self, terminator: N, required: bool) -> Self 
+      where N: Into<Cow<'def, str>>
+    {
+        match self.kind {
+            ArgDefKind::Collect { terminator: ref mut t, .. } => *t = Some((terminator.into(), required)),
+            _ => self.warn("Only 'collect' arguments can be terminated (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Makes a collector also count the values it adds, in the given counter.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let mut includes: Vec<String> = Vec::new();
+    /// let mut include_count = 0;
+    /// parse_plain("prog", &["-I", "src", "-I", "lib"], vec![
+    ///     ArgDef::collect("include", &mut includes).short("I").count_into(&mut include_count),
+    /// ]).unwrap();
+    /// assert_eq!(2, include_count);
+    /// assert_eq!(vec!["src", "lib"], includes);
+    /// ```
+    pub fn count_into(mut self, counter: &'tar mut usize) -> Self {
+        match self.kind {
+            ArgDefKind::Collect { counter: ref mut c, .. } => *c = Some(counter),
+            _ => self.warn("Only 'collect' arguments can count their values (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Makes a collector or trail split each of its values on the given 
+    /// delimiter, and add the parts separately.
+    ///
+    /// Values can then be given in one or more arguments, and each is split:
+    /// `prog 1,2 3` => `vec![1, 2, 3]`.
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        match self.kind {
+            ArgDefKind::Collect { delimiter: ref mut d, .. } 
+            | ArgDefKind::Trail { delimiter: ref mut d, .. } => *d = Some(delimiter),
+            _ => self.warn("Only 'collect' and 'trail' arguments can have a delimiter (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Makes a collector take a list of values, separated by the delimiter,
+    /// by whitespace (as separate arguments) or both, so `--items a,b`, 
+    /// `--items a b` and `--items a, b` all give `["a", "b"]`.
+    ///
+    /// This combines `variadic` and `delimiter`: the arguments up to the next
+    /// option are taken, and each is split on the delimiter, so 
+    /// `--items a,b c` gives `["a", "b", "c"]`. Empty parts are skipped, as 
+    /// they come from a delimiter next to a space (`a, b`), or at the end
+    /// (`a,`). A value attached with `=` is split, but taken alone, so 
+    /// `--items=a,b c` leaves `c` for the positional arguments.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// for args in &[&["--items", "a,b", "c"][..], &["--items", "a", "b,c"], &["--items", "a,", "b", ",c"]] {
+    ///     let mut items: Vec<String> = Vec::new();
+    ///     parse_plain("prog", args, vec![
+    ///         ArgDef::collect("items", &mut items).list(','),
+    ///     ]).unwrap();
+    ///     assert_eq!(vec!["a", "b", "c"], items);
+    /// }
+    ///
+    /// let (mut items, mut file): (Vec<String>, String) = (Vec::new(), String::new());
+    /// parse_plain("prog", &["--items=a,b", "c"], vec![
+    ///     ArgDef::collect("items", &mut items).list(','),
+    ///     ArgDef::positional("file", &mut file),
+    /// ]).unwrap();
+    /// assert_eq!((vec!["a".to_string(), "b".to_string()], "c".to_string()), (items, file));
+    /// ```
+    pub fn list(mut self, delimiter: char) -> Self {
+        match self.kind {
+            ArgDefKind::Collect { .. } => {
+                self.list = true;
+                self.variadic().delimiter(delimiter)
+            }
+            _ => {
+                self.warn("Only 'collect' arguments can take lists (ArgDef error)");
+                self
+            }
+        }
+    }
+    
+    /// Makes a collector read key-value pairs into a map, with several pairs
+    /// in each value: `--header 'A: 1; B: 2'` for `.pairs(';', ':')`.
+    ///
+    /// Each value is split on the pair separator, and each pair on the first 
+    /// key separator, with the whitespace around keys and values trimmed. A 
+    /// pair without the key separator (including an empty one) fails the 
+    /// parse. The target must be a map, like a `HashMap` or `BTreeMap`.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// # use std::collections::BTreeMap;
+    /// let mut headers: BTreeMap<String, u32> = BTreeMap::new();
+    /// parse_plain("prog", &["--header", "A: 1; B: 2", "--header", "C: 3"], vec![
+    ///     ArgDef::collect("header", &mut headers).pairs(';', ':'),
+    /// ]).unwrap();
+    /// let expected = vec![("A", 1), ("B", 2), ("C", 3)].into_iter()
+    ///     .map(|(k, v)| (k.to_string(), v)).collect::<BTreeMap<_, _>>();
+    /// assert_eq!(expected, headers);
+    /// ```
+    pub fn pairs(mut self, pair_separator: char, key_separator: char) -> Self {
+        match self.kind {
+            ArgDefKind::Collect { ref mut delimiter, key_separator: ref mut k, .. } => {
+                *delimiter = Some(pair_separator);
+                *k = Some(key_separator);
+            }
+            _ => self.warn("Only 'collect' arguments can read key-value pairs (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Limits how many values a trail takes: at least `min`, and at most 
+    /// `max` if it is given. This replaces the `optional` setting of the 
+    /// trail.
+    ///
+    /// The bounds are shown in usage and help messages, with the minimum 
+    /// written out when there is no maximum:
+    ///
+    /// ```
+    /// # use argonaut::{ArgDef, Help};
+    /// let mut files: Vec<String> = Vec::new();
+    /// let usage = |min, max, files: &mut Vec<String>| {
+    ///     let defs = vec![ArgDef::trail("file", true, files).bounds(min, max)];
+    ///     Help::new("prog".into(), &defs).usage_message()
+    /// };
+    /// assert_eq!("prog [file...]", usage(0, None, &mut files));
+    /// assert_eq!("prog file file [file...]", usage(2, None, &mut files));
+    /// assert_eq!("prog file (2-5)", usage(2, Some(5), &mut files));
+    /// assert_eq!("prog file (3)", usage(3, Some(3), &mut files));
+    /// ```
+    pub fn bounds(mut self, min: usize, max: Option<usize>) -> Self {
+        match self.kind {
+            ArgDefKind::Trail { ref mut bounds, .. } => *bounds = Some((min, max)),
+            _ => self.warn("Only 'trail' arguments can have bounds (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Makes the parse fail when this option isn't given (and has no default,
+    /// or no environment variable that is set, see `env`).
+    pub fn required(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Positional { .. } | ArgDefKind::Command { .. } | ArgDefKind::Trail { .. } 
+            | ArgDefKind::Subcommand { .. } | ArgDefKind::Interrupt { .. } => {
+                self.warn("Only options that aren't interrupts can be required (ArgDef error)");
+            }
+            _ => self.required = true,
+        }
+        self
+    }
+    
+    /// Hides this option from help messages, except for the advanced help 
+    /// (see `Help::advanced_help_message`).
+    pub fn hidden(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Positional { .. } | ArgDefKind::Command { .. } | ArgDefKind::Trail { .. } 
+            | ArgDefKind::Subcommand { .. } => {
+                self.warn("Only options can be hidden (ArgDef error)");
+            }
+            _ => self.hidden = true,
+        }
+        self
+    }
+    
+    /// Removes the whitespace around the values of this positional argument,
+    /// setting or collector before they are parsed, so `" 42 "` reads as 42.
+    ///
+    /// This also trims default values, and each value of a collector with a
+    /// delimiter. A value that is only whitespace becomes empty.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let mut port = None::<u16>;
+    /// let result = parse_plain("prog", &["--port", " 42 "], vec![
+    ///     ArgDef::setting("port", &mut port),
+    /// ]);
+    /// assert!(result.is_err());
+    ///
+    /// let mut sizes: Vec<u32> = Vec::new();
+    /// parse_plain("prog", &["--port", " 42 ", "--size=1, 2"], vec![
+    ///     ArgDef::setting("port", &mut port).trim(),
+    ///     ArgDef::collect("size", &mut sizes).delimiter(',').trim(),
+    /// ]).unwrap();
+    /// assert_eq!(Some(42), port);
+    /// assert_eq!(vec![1, 2], sizes);
+    /// ```
+    pub fn trim(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Positional { .. } | ArgDefKind::Setting { .. } | ArgDefKind::Collect { .. } => {
+                self.trim = true;
+            }
+            _ => self.warn("Only positional arguments, settings and collectors can be trimmed (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Makes the parse fail when this positional argument or setting is given
+    /// an empty value, like an unquoted empty shell variable would give.
+    pub fn non_empty(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Positional { .. } | ArgDefKind::Command { .. } | ArgDefKind::Setting { .. } => {
+                self.non_empty = true;
+            }
+            _ => self.warn("Only positional arguments and settings can be non-empty (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Makes the parse fail when this positional argument or setting is given
+    /// a number that isn't greater than zero, like a size or a port.
+    ///
+    /// The value is checked as it is given, before it is parsed into the 
+    /// target. Values that aren't numbers are left to the target to reject.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let mut jobs = None::<i32>;
+    /// let result = parse_plain("prog", &["--jobs", "0"], vec![
+    ///     ArgDef::setting("jobs", &mut jobs).positive(),
+    /// ]);
+    /// assert_eq!("Argument 'jobs' must be positive, not '0'", 
+    ///            result.unwrap_err().to_string());
+    /// ```
+    pub fn positive(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Positional { .. } | ArgDefKind::Setting { .. } => {
+                self.sign = Some(Sign::Positive);
+            }
+            _ => self.warn("Only positional arguments and settings can be positive (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Makes the parse fail when this positional argument or setting is given
+    /// a number below zero, like `-1` for a count. Zero is allowed.
+    ///
+    /// This is checked like `positive`.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let mut offset = None::<f64>;
+    /// let result = parse_plain("prog", &["--offset", "-0.5"], vec![
+    ///     ArgDef::setting("offset", &mut offset).non_negative(),
+    /// ]);
+    /// assert_eq!("Argument 'offset' must not be negative, not '-0.5'", 
+    ///            result.unwrap_err().to_string());
+    ///
+    /// let mut offset = None::<f64>;
+    /// parse_plain("prog", &["--offset", "0"], vec![
+    ///     ArgDef::setting("offset", &mut offset).non_negative(),
+    /// ]).unwrap();
+    /// assert_eq!(Some(0.0), offset);
+    /// ```
+    pub fn non_negative(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Positional { .. } | ArgDefKind::Setting { .. } => {
+                self.sign = Some(Sign::NonNegative);
+            }
+            _ => self.warn("Only positional arguments and settings can be non-negative (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Makes the parse fail when this option is given before a subcommand, 
+    /// like a global `--dry-run` that none of the subcommands support.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let mut dry_run = false;
+    /// let result = parse_plain("prog", &["--dry-run", "build"], vec![
+    ///     ArgDef::flag("dry-run", &mut dry_run).without_subcommand(),
+    ///     ArgDef::subcommand("build", |_, _| Ok(None)),
+    /// ]);
+    /// assert_eq!("--dry-run cannot be combined with subcommand 'build'", 
+    ///            result.unwrap_err().to_string());
+    /// ```
+    ///
+    /// The option can still be given without a subcommand, if none is 
+    /// required (eg. when an interrupt like `--version` continues the parse).
+    pub fn without_subcommand(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Positional { .. } | ArgDefKind::Command { .. } | ArgDefKind::Trail { .. } 
+            | ArgDefKind::Subcommand { .. } => {
+                self.warn("Only options can be kept from subcommands (ArgDef error)");
+            }
+            _ => self.without_subcommand = true,
+        }
+        self
+    }
+    
+    /// Restricts this option to the given subcommand, so the parse fails when
+    /// it is given before another one. This can be used several times, to 
+    /// allow the option with each of the subcommands.
+    ///
+    /// This is for options that the program reads itself, but that only make
+    /// sense for some subcommands, like `prog --force delete x`. Options 
+    /// without a scope are global, and can be given with any subcommand. An
+    /// option that a subcommand reads itself (after its name) should instead
+    /// be defined by its handler. The subcommand must be defined.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let mut force = false;
+    /// let result = parse_plain("prog", &["--force", "list"], vec![
+    ///     ArgDef::flag("force", &mut force).scope("delete"),
+    ///     ArgDef::subcommand("delete", |_, _| Ok(None)),
+    ///     ArgDef::subcommand("list", |_, _| Ok(None)),
+    /// ]);
+    /// assert_eq!("Option '--force' can only be used with the subcommand 'delete', not 'list'", 
+    ///            result.unwrap_err().to_string());
+    /// ```
+    pub fn scope<N>(mut self, subcommand: N) -> Self where N: Into<Cow<'def, str>> {
+        match self.kind {
+            ArgDefKind::Positional { .. } | ArgDefKind::Command { .. } | ArgDefKind::Trail { .. } 
+            | ArgDefKind::Subcommand { .. } => {
+                self.warn("Only options can be scoped to subcommands (ArgDef error)");
+            }
+            _ => self.scopes.push(subcommand.into()),
+        }
+        self
+    }
+    
+    /// Gives this setting or positional argument a default value, which is 
+    /// parsed into the target when the parse is done, if the argument wasn't
+    /// given.
+    ///
+    /// For settings, this works like a computed default (see `default_with`)
+    /// that always gives the value, so the defaults of the config win over 
+    /// it, and a required setting with a default doesn't have to be given.
+    /// The help shows the value as `[default: value]`. A default that can't
+    /// be parsed into the target makes the definitions invalid.
+    ///
+    /// A positional argument with a default is optional, and shown as 
+    /// `[name]` in the usage. The values are given to the positionals in the
+    /// order they were defined, so the optional ones must be defined after
+    /// all the required ones.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, Help, parse_plain, ParseError};
+    /// let mut port: Option<u16> = None;
+    /// parse_plain("prog", &["-v"], vec![
+    ///     ArgDef::flag("verbose", &mut false).short("v"),
+    ///     ArgDef::setting("port", &mut port).default("8080"),
+    /// ]).unwrap();
+    /// assert_eq!(Some(8080), port);
+    ///
+    /// let defs = vec![ArgDef::setting("port", &mut port).default("8080")];
+    /// assert!(Help::new("prog".into(), &defs).help_message("").contains("--port PORT [default: 8080]"));
+    ///
+    /// match parse_plain("prog", &["--port", "80"], vec![
+    ///     ArgDef::setting("port", &mut port).default("http"),
+    /// ]) {
+    ///     Err(ParseError::InvalidDefinitions(msg)) => assert_eq!(
+    ///         "Invalid default 'http' for 'port': Could not parse and convert 'http' into u16", msg),
+    ///     _ => panic!("The default should be invalid"),
+    /// }
+    ///
+    /// let (mut input, mut output) = (String::new(), String::new());
+    /// parse_plain("prog", &["main.c"], vec![
+    ///     ArgDef::positional("input", &mut input),
+    ///     ArgDef::positional("output", &mut output).default("a.out"),
+    /// ]).unwrap();
+    /// assert_eq!(("main.c", "a.out"), (input.as_str(), output.as_str()));
+    ///
+    /// let defs = vec![
+    ///     ArgDef::positional("input", &mut input),
+    ///     ArgDef::positional("output", &mut output).default("a.out"),
+    /// ];
+    /// assert_eq!("prog input [output]", Help::new("prog".into(), &defs).usage_message());
+    /// ```
+    pub fn default<V>(mut self, value: V) -> Self where V: Into<Cow<'def, str>> {
+        match self.kind {
+            ArgDefKind::Setting { .. } => {
+                let value = value.into();
+                let given = value.to_string();
+                self.default_with = Some(Box::new(move |_| given.clone()));
+                self.default = Some(value);
+            }
+            ArgDefKind::Positional { .. } => self.default = Some(value.into()),
+            _ => self.warn("Only settings and positional arguments can have default values (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Gives this setting a default that is computed when the parse is done,
+    /// if the setting wasn't given.
+    ///
+    /// The callback gets the values of the positional arguments and settings
+    /// (see `DefaultContext`), so a default can be derived from other 
+    /// arguments. The defaults of the config are applied first, and computed
+    /// defaults then follow in the order they were defined, so a computed 
+    /// default can use the settings defined before it. A required setting 
+    /// with a computed default doesn't have to be given.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let mut input = String::new();
+    /// let mut output: Option<String> = None;
+    /// parse_plain("prog", &["report.md"], vec![
+    ///     ArgDef::positional("input", &mut input),
+    ///     ArgDef::setting("output", &mut output).default_with(|context| {
+    ///         context.get("input").unwrap_or("out").replace(".md", ".html")
+    ///     }),
+    /// ]).unwrap();
+    /// assert_eq!(Some("report.html".to_string()), output);
+    /// ```
+    pub fn default_with<F>(mut self, default: F) -> Self 
+      where F: FnMut(&DefaultContext) -> String + 'def
+    {
+        match self.kind {
+            ArgDefKind::Setting { .. } => self.default_with = Some(Box::new(default)),
+            _ => self.warn("Only settings can have computed defaults (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Makes this flag, count or interrupt take effect before the other 
+    /// arguments are read, so that it works even when they fail the parse.
+    ///
+    /// Eager options are read in a first pass over the arguments (up to 
+    /// `--`), in the order they are given. The other arguments are then read
+    /// as usual, skipping the eager options. So `--verbose` can enable 
+    /// logging for the diagnostics of the parse, and an eager `--help` is 
+    /// shown even when other arguments are wrong. The values of other 
+    /// options are skipped in the first pass, so `--name --verbose` still 
+    /// gives the value `--verbose` to a `--name` setting.
+    ///
+    /// Eager options are read in order as usual by `parse_partial`, since 
+    /// the arguments after the ones it reads aren't its own.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let mut verbose = false;
+    /// let result = parse_plain("prog", &["--bad", "--verbose"], vec![
+    ///     ArgDef::flag("verbose", &mut verbose).eager(),
+    /// ]);
+    /// assert!(result.is_err());
+    /// assert!(verbose);
+    /// ```
+    pub fn eager(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Flag { .. } | ArgDefKind::Count { .. } | ArgDefKind::Interrupt { .. } => {
+                self.eager = true;
+            }
+            _ => self.warn("Only flags, counts and interrupts can be eager (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Lets the value of this setting refer to the values of other settings
+    /// and positional arguments, like `--out '{name}-{version}.txt'`.
+    ///
+    /// Each `{name}` is replaced with the value of the argument with that 
+    /// name, as it was given (or defaulted), when the parse is done. It may 
+    /// itself refer to other arguments, if it is also templated. Use `{{` and 
+    /// `}}` for literal braces. The parse fails if a placeholder names an 
+    /// argument without a value, if a brace isn't closed, or if templates 
+    /// refer back to themselves.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let mut name = String::new();
+    /// let mut out: Option<String> = None;
+    /// parse_plain("prog", &["report", "--out", "{name}.txt"], vec![
+    ///     ArgDef::positional("name", &mut name),
+    ///     ArgDef::setting("out", &mut out).templated(),
+    /// ]).unwrap();
+    /// assert_eq!(Some("report.txt".to_string()), out);
+    /// ```
+    pub fn templated(mut self) -> Self {
+        match self.kind {
+            ArgDefKind::Setting { .. } => self.templated = true,
+            _ => self.warn("Only settings can be templated (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Describes the type of the values this argument takes, like `integer`.
+    ///
+    /// This is only used for help messages, as `--port PORT (integer)`.
+    pub fn value_type<N>(mut self, value_type: N) -> Self where N: Into<Cow<'def, str>> {
+        match self.kind {
+            ArgDefKind::Positional { .. } | ArgDefKind::Command { .. } | ArgDefKind::Trail { .. } 
+            | ArgDefKind::Setting { .. } | ArgDefKind::Collect { .. } => {
+                self.value_type = Some(value_type.into());
+            }
+            _ => self.warn("Only arguments that take values can have a value type (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Restricts the values of this trail to the given choices, like
+    /// `&["red", "green", "blue"]`.
+    ///
+    /// Each trailing value is checked before it is parsed, and a value that 
+    /// isn't one of the choices fails the parse, with the choices listed.
+    pub fn choices(mut self, choices: &[&'def str]) -> Self {
+        match self.kind {
+            ArgDefKind::Trail { .. } => {
+                self.choices = choices.iter().map(|&choice| Cow::Borrowed(choice)).collect();
+            }
+            _ => self.warn("Only trails can have choices (ArgDef error)"),
+        }
+        self
+    }
+    
+    /// Like `choices`, but the values are matched regardless of case, and
+    /// the matched choice is used as it is spelled here.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let mut modes: Vec<String> = Vec::new();
+    /// parse_plain("prog", &["FAST", "Safe", "fast"], vec![
+    ///     ArgDef::trail("modes", false, &mut modes).choices_ci(&["fast", "safe"]),
+    /// ]).unwrap();
+    /// assert_eq!(modes, vec!["fast", "safe", "fast"]);
+    /// ```
+    pub fn choices_ci(mut self, choices: &[&'def str]) -> Self {
+        self = self.choices(choices);
+        if let ArgDefKind::Trail { .. } = self.kind {
+            self.choices_ignore_case = true;
+        }
+        self
+    }
+    
+    /// Makes this option require another option (by its long name) to also
+    /// be given, when it is given.
+    ///
+    /// Requirements follow each other, so if `a` requires `b` and `b` 
+    /// requires `c`, giving `a` also requires `c`. Options that could then
+    /// never be given, because they require options that conflict, make the
+    /// definitions invalid.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, ParseError, parse_plain};
+    /// let (mut a, mut b, mut c) = (false, false, false);
+    /// let result = parse_plain("prog", &["--a"], vec![
+    ///     ArgDef::flag("a", &mut a).requires("b"),
+    ///     ArgDef::flag("b", &mut b).conflicts("a"),
+    /// ]);
+    /// assert!(matches!(result, Err(ParseError::InvalidDefinitions(_))));
+    ///
+    /// let result = parse_plain("prog", &["--a"], vec![
+    ///     ArgDef::flag("a", &mut a).requires("b"),
+    ///     ArgDef::flag("b", &mut b).requires("c"),
+    ///     ArgDef::flag("c", &mut c).conflicts("a"),
+    /// ]);
+    /// assert_eq!("Invalid definitions: Option 'a' can never be given, as it requires 'c' and 'a', which conflict.", 
+    ///            result.unwrap_err().to_string());
+    ///
+    /// let result = parse_plain("prog", &["--a"], vec![
+    ///     ArgDef::flag("a", &mut a).requires("missing"),
+    /// ]);
+    /// assert!(matches!(result, Err(ParseError::InvalidDefinitions(_))));
+    /// ```
+    pub fn requires<N>(mut self, option: N) -> Self where N: Into<Cow<'def, str>> {
+        self.requires.push(option.into());
+        self
+    }
+    
+    /// Makes this option and another one (by its long name) unusable together.
+    pub fn conflicts<N>(mut self, option: N) -> Self where N: Into<Cow<'def, str>> {
+        self.conflicts.push(option.into());
+        self
+    }
+    
+    /// Makes this option and another one (by its long name) only usable 
+    /// together: either both are given, or neither is.
+    ///
+    /// Unlike `requires`, this goes both ways, so `--min` without `--max` 
+    /// fails just like `--max` without `--min`.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let parse = |args: &[&str]| {
+    ///     let (mut min, mut max): (Option<u32>, Option<u32>) = (None, None);
+    ///     parse_plain("prog", args, vec![
+    ///         ArgDef::setting("min", &mut min).together_with("max"),
+    ///         ArgDef::setting("max", &mut max),
+    ///     ]).is_ok()
+    /// };
+    /// assert!(parse(&[]));
+    /// assert!(parse(&["--min", "1", "--max", "5"]));
+    /// assert!(! parse(&["--min", "1"]));
+    /// assert!(! parse(&["--max", "5"]));
+    /// ```
+    pub fn together_with<N>(mut self, option: N) -> Self where N: Into<Cow<'def, str>> {
+        self.together.push(option.into());
+        self
+    }
+    
+    /// Makes this option also set a flag (by its long name) when it is given.
+    ///
+    /// Implications follow each other, so if `--trace` implies `debug` and
+    /// `--debug` implies `verbose`, `--trace` sets all three. Implied flags
+    /// count as given, also for `requires` and `conflicts`. A flag that is 
+    /// given as false (with `+` toggles) implies nothing.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain};
+    /// let (mut trace, mut debug, mut verbose) = (false, false, false);
+    /// parse_plain("prog", &["--trace"], vec![
+    ///     ArgDef::flag("trace", &mut trace).implies("debug"),
+    ///     ArgDef::flag("debug", &mut debug).implies("verbose"),
+    ///     ArgDef::flag("verbose", &mut verbose),
+    /// ]).unwrap();
+    /// assert_eq!((true, true, true), (trace, debug, verbose));
+    ///
+    /// // Implications in a cycle end once every flag in it is set.
+    /// let (mut trace, mut debug) = (false, false);
+    /// parse_plain("prog", &["--debug"], vec![
+    ///     ArgDef::flag("trace", &mut trace).implies("debug"),
+    ///     ArgDef::flag("debug", &mut debug).implies("trace"),
+    /// ]).unwrap();
+    /// assert_eq!((true, true), (trace, debug));
+    /// ```
+    pub fn implies<N>(mut self, flag: N) -> Self where N: Into<Cow<'def, str>> {
+        self.implies.push(flag.into());
+        self
+    }
+    
+    /// Adds a help description for this argument.
+    /// 
+    /// This is only used for help messages.
+    pub fn help<N>(mut self, help: N) -> Self where N: Into<Cow<'def, str>> {
+        self.help_desc = Some(help.into());
+        self
+    }
+    
+    /// Adds a longer help description for this argument, which is shown by
+    /// the full help message instead of the one from `help`.
+    ///
+    /// The one from `help` is still used for brief listings, like Markdown 
+    /// documentation. If only one of the two is given, it is used for both.
+    pub fn help_long<N>(mut self, help: N) -> Self where N: Into<Cow<'def, str>> {
+        self.help_long = Some(help.into());
+        self
+    }
+    
+    /// Adds this definition to the given list and returns a handle to it.
+    ///
+    /// The handle can be used to query the `ParseSummary` of the parse, 
+    /// instead of referring to the argument by its name.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, ParseConfig, parse_plain_with};
+    /// let mut verbose = false;
+    /// let mut defs = Vec::new();
+    /// let verbose_handle = ArgDef::flag("verbose", &mut verbose).register(&mut defs);
+    /// let summary = parse_plain_with("prog", &["--verbose"], defs, ParseConfig::new()).unwrap();
+    /// assert!(summary.was_given(verbose_handle));
+    /// ```
+    pub fn register(self, definitions: &mut Vec<ArgDef<'def, 'tar>>) -> OptHandle {
+        definitions.push(self);
+        OptHandle(definitions.len() - 1)
+    }
+}
+
+/// Returns the address of the given target, unless it has no size (and thus
+/// no unique address).
+fn address<T: ?Sized>(target: &T) -> Option<*const u8> {
+    if mem::size_of_val(target) == 0 {
+        None
+    } else {
+        Some(target as *const T as *const u8)
+    }
+}
+
+impl<'def, 'tar> ArgDef<'def, 'tar> {
+    /// Returns the short identifier of this argument, if it has one.
+    pub(crate) fn short_id(&self) -> Option<&str> {
+        use self::ArgDefKind::*;
+        match self.kind {
+            Flag { ref short, .. }
+            | Count { ref short, .. }
+            | Features { ref short, .. }
+            | Setting { ref short, .. }
+            | Interrupt { ref short, .. }
+            | Collect { ref short, .. } => short.as_ref().map(|short| short.as_ref()),
+            Positional { .. } | Command { .. } | Trail { .. } | Subcommand { .. } => None,
+        }
+    }
+    
+    /// Returns whether this argument is an option that can get a default 
+    /// value (see `ParseConfig::defaults`).
+    pub(crate) fn takes_default(&self) -> bool {
+        use self::ArgDefKind::*;
+        match self.kind {
+            Flag { .. } | Count { .. } | Features { .. } | Setting { .. } | Collect { .. } => true,
+            Positional { .. } | Command { .. } | Trail { .. } | Subcommand { .. } | Interrupt { .. } => false,
+        }
+    }
+    
+    /// Returns the address of the variable this argument writes to, if any.
+    pub(crate) fn target_address(&self) -> Option<*const u8> {
+        use self::ArgDefKind::*;
+        match self.kind {
+            Positional { ref target } | Command { ref target, .. } => address(&**target),
+            Trail { ref target, .. } | Collect { ref target, .. } => address(&**target),
+            Flag { ref target, .. } => address(&**target),
+            Count { ref target, .. } => address(&**target),
+            Features { ref target, .. } => address(&**target),
+            Setting { ref target, .. } => address(&**target),
+            Subcommand { .. } | Interrupt { .. } => None,
+        }
+    }
+}
+
+/// A handle to a registered argument definition.
+///
+/// This is just the index of the definition in the list given to the parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OptHandle(pub(crate) usize);
+
+/// Allows every type that is FromStr to be read from an argument.
+pub trait SingleTarget: Debug {
+    /// Parses the value and updates self with it.
+    fn parse(&mut self, value: &str) -> Result<(), String>;
+    
+    /// Checks that the value can be parsed, without updating self, for 
+    /// default values (see `ArgDef::default`).
+    ///
+    /// This accepts every value by default.
+    fn check(&self, _value: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl<T> SingleTarget for T where T: Debug + FromStr {
+    fn parse(&mut self, value: &str) -> Result<(), String> {
+        *self = parse_value(value)?;
+        Ok(())
+    }
+    
+    fn check(&self, value: &str) -> Result<(), String> {
+        parse_value::<T>(value).map(|_| ())
+    }
+}
+
+/// Parses a value for a target, with an error that names the type, like
+/// "Could not parse and convert '10.0.0.1:x' into SocketAddr".
+///
+/// This is used by `impl_collection_target`, and is not meant to be used 
+/// otherwise.
+#[doc(hidden)]
+pub fn parse_value<T: FromStr>(value: &str) -> Result<T, String> {
+    <T as FromStr>::from_str(value)
+        .map_err(|_| format!("Could not parse and convert '{}' into {}", value, type_name::<T>()))
+}
+
+/// Returns the name of the type without its module paths, like 
+/// `Option<String>`.
+fn type_name<T>() -> String {
+    // This needs Rust 1.38, which is covered by the `rust-version` of the crate.
+    let mut name = String::new();
+    let full = ::std::any::type_name::<T>();
+    let mut rest = full;
+    while let Some(index) = rest.find("::") {
+        name.push_str(&rest[..index]);
+        let path_start = name.trim_end_matches(|c: char| c.is_alphanumeric() || c == '_').len();
+        name.truncate(path_start);
+        rest = &rest[index + 2..];
+    }
+    name.push_str(rest);
+    name
+}
+
+/// Allows every type that is FromStr to be read from an argument.
+pub trait OptionTarget: Debug {
+    /// Parses the value and updates self with it.
+    fn parse(&mut self, value: &str) -> Result<(), String>;
+    
+    /// Checks that the value can be parsed, without updating self, for 
+    /// default values (see `ArgDef::default`).
+    ///
+    /// This accepts every value by default.
+    fn check(&self, _value: &str) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl<T> OptionTarget for Option<T> where T: Debug + FromStr {
+    fn parse(&mut self, value: &str) -> Result<(), String> {
+        *self = Some(parse_value(value)?);
+        Ok(())
+    }
+    
+    fn check(&self, value: &str) -> Result<(), String> {
+        parse_value::<T>(value).map(|_| ())
+    }
+}
+
+/// Allows a collection to be extended with values read from arguments.
+pub trait CollectionTarget: Debug {
+    /// Parses the value and adds it to this collection.
+    fn parse_and_add(&mut self, value: &str) -> Result<(), String>;
+    
+    /// Parses the key and the value, and adds them to this collection, for
+    /// collectors that read key-value pairs (see `ArgDef::pairs`).
+    ///
+    /// Only maps can take pairs, so this fails by default.
+    fn parse_and_add_pair(&mut self, key: &str, value: &str) -> Result<(), String> {
+        Err(format!("Could not add the pair '{}' and '{}', as the target is not a map", key, value))
+    }
+}
+
+/// Implements `CollectionTarget` for a collection type that is generic over
+/// its items, using the given method to add each parsed value.
+///
+/// This makes collections from other crates usable as targets for 
+/// collectors and trails. Extra bounds needed by the item type (like 
+/// `Hash + Eq` for a hash set) can be given after the method; `Debug` and
+/// `FromStr` are always required. The collection can be named by a path,
+/// like `stack::Stack`.
+///
+/// The macro must be used in the crate that defines either the collection or
+/// the trait, as usual for trait impls. A collection from another crate is 
+/// used through a local newtype around it, with a method that adds an item
+/// (see `examples/collection.rs`).
+///
+/// # Example
+/// ```
+/// #[macro_use] extern crate argonaut;
+/// use argonaut::{ArgDef, parse_plain};
+///
+/// mod stack {
+///     #[derive(Debug, Default)]
+///     pub struct Stack<T> { pub items: Vec<T> }
+///
+///     impl<T> Stack<T> {
+///         pub fn put(&mut self, item: T) { self.items.push(item); }
+///     }
+/// }
+/// use stack::Stack;
+///
+/// impl_collection_target!(stack::Stack, put);
+///
+/// fn main() {
+///     let mut numbers: Stack<u32> = Stack::default();
+///     parse_plain("prog", &["1", "2"], vec![
+///         ArgDef::trail("numbers", true, &mut numbers),
+///     ]).unwrap();
+///     assert_eq!(vec![1, 2], numbers.items);
+/// }
+/// ```
+#[macro_export]
+macro_rules! impl_collection_target {
+    ($($collection:ident)::+, $method:ident) => {
+        impl<T> $crate::CollectionTarget for $($collection)::+<T> 
+          where T: ::std::fmt::Debug + ::std::str::FromStr 
+        {
+            fn parse_and_add(&mut self, value: &str) -> Result<(), String> {
+                let value = $crate::parse_value::<T>(value)?;
+                self.$method(value);
+                Ok(())
+            }
+        }
+    };
+    ($($collection:ident)::+, $method:ident, $($bound:tt)+) => {
+        impl<T> $crate::CollectionTarget for $($collection)::+<T> 
+          where T: ::std::fmt::Debug + ::std::str::FromStr + $($bound)+ 
+        {
+            fn parse_and_add(&mut self, value: &str) -> Result<(), String> {
+                let value = $crate::parse_value::<T>(value)?;
+                self.$method(value);
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_collection_target!(Vec, push);
+impl_collection_target!(BinaryHeap, push, Ord);
+impl_collection_target!(BTreeSet, insert, Ord);
+impl_collection_target!(HashSet, insert, Hash + Eq);
+impl_collection_target!(LinkedList, push_back);
+impl_collection_target!(VecDeque, push_back);
+
+/// Parses a key and a value for a map target.
+fn parse_pair<K: FromStr, V: FromStr>(key: &str, value: &str) -> Result<(K, V), String> {
+    let key = match <K as FromStr>::from_str(key) {
+        Ok(key) => key,
+        Err(_) => return Err(format!("Could not parse and convert the key '{}' into {}", key, type_name::<K>())),
+    };
+    Ok((key, parse_value(value)?))
+}
 
-impl<T> CollectionTarget for VecDeque<T> where T: Debug + FromStr {
+/// Maps take values as `key=value`, or pairs with other separators (see 
+/// `ArgDef::pairs`).
+impl<K, V> CollectionTarget for HashMap<K, V> 
+  where K: Debug + FromStr + Hash + Eq, V: Debug + FromStr 
+{
     fn parse_and_add(&mut self, value: &str) -> Result<(), String> {
-        let value = match <T as FromStr>::from_str(value) {
-            Ok(val) => val,
-            Err(_) => return Err(format!("Could not parse and convert '{}'", value)),
-        };
-        self.push_back(value);
+        match value.find('=') {
+            Some(index) => self.parse_and_add_pair(&value[..index], &value[index + 1..]),
+            None => Err(format!("Expected a 'key=value' pair, not '{}'", value)),
+        }
+    }
+    
+    fn parse_and_add_pair(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let (key, value) = parse_pair(key, value)?;
+        self.insert(key, value);
+        Ok(())
+    }
+}
+
+impl<K, V> CollectionTarget for BTreeMap<K, V> 
+  where K: Debug + FromStr + Ord, V: Debug + FromStr 
+{
+    fn parse_and_add(&mut self, value: &str) -> Result<(), String> {
+        match value.find('=') {
+            Some(index) => self.parse_and_add_pair(&value[..index], &value[index + 1..]),
+            None => Err(format!("Expected a 'key=value' pair, not '{}'", value)),
+        }
+    }
+    
+    fn parse_and_add_pair(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let (key, value) = parse_pair(key, value)?;
+        self.insert(key, value);
+        Ok(())
+    }
+}
+
+/// A collection target that appends every value to a string, with the given
+/// separator between them.
+///
+/// The separator is only added when the string isn't empty, so text already
+/// in it is kept as the first part.
+///
+/// # Example
+/// ```
+/// # use argonaut::{ArgDef, Joined, parse_plain};
+/// let mut text = String::new();
+/// {
+///     let mut lines = Joined(&mut text, "\n");
+///     parse_plain("prog", &["--line", "one", "--line", "two"], vec![
+///         ArgDef::collect("line", &mut lines),
+///     ]).unwrap();
+/// }
+/// assert_eq!("one\ntwo", text);
+/// ```
+#[derive(Debug)]
+pub struct Joined<'a, 's>(pub &'a mut String, pub &'s str);
+
+impl<'a, 's> CollectionTarget for Joined<'a, 's> {
+    fn parse_and_add(&mut self, value: &str) -> Result<(), String> {
+        if ! self.0.is_empty() {
+            self.0.push_str(self.1);
+        }
+        self.0.push_str(value);
+        Ok(())
+    }
+}
+
+/// A collection target that stores each value in a map, by its position 
+/// among the values, like `0 => first, 1 => second`.
+///
+/// This is meant for trails, where the key is then the index of the argument
+/// in the trail (leaving out positionals and options). The indices continue
+/// after the largest key already in the map, so earlier entries are kept. 
+/// Whether the trail is optional decides as usual if it can be empty.
+///
+/// # Example
+/// ```
+/// # use argonaut::{ArgDef, Indexed, parse_plain};
+/// # use std::collections::BTreeMap;
+/// let (mut command, mut args) = (String::new(), BTreeMap::new());
+/// {
+///     let mut indexed = Indexed::new(&mut args);
+///     parse_plain("prog", &["echo", "-v", "hi", "there"], vec![
+///         ArgDef::positional("command", &mut command),
+///         ArgDef::flag("verbose", &mut false).short("v"),
+///         ArgDef::trail("args", true, &mut indexed),
+///     ]).unwrap();
+/// }
+/// assert_eq!(args.get(&0), Some(&"hi".to_string()));
+/// assert_eq!(args.get(&1), Some(&"there".to_string()));
+/// ```
+#[derive(Debug)]
+pub struct Indexed<'a, T: 'a> {
+    map: &'a mut BTreeMap<usize, T>,
+    next: usize,
+}
+
+impl<'a, T> Indexed<'a, T> {
+    /// Creates a target that adds values to the map, with indices that come
+    /// after its current keys.
+    pub fn new(map: &'a mut BTreeMap<usize, T>) -> Indexed<'a, T> {
+        let next = map.keys().next_back().map_or(0, |last| last + 1);
+        Indexed { map, next }
+    }
+}
+
+impl<'a, T> CollectionTarget for Indexed<'a, T> where T: Debug + FromStr {
+    fn parse_and_add(&mut self, value: &str) -> Result<(), String> {
+        // A value that fails to parse still takes its index.
+        let index = self.next;
+        self.next += 1;
+        self.map.insert(index, parse_value(value)?);
+        Ok(())
+    }
+}
+
+/// A target that takes one of a set of names, and sets a variable to the 
+/// value paired with it (see `ArgDef::pos_enum`).
+///
+/// This works for any `Clone` type, so the values don't have to implement
+/// `FromStr`. Names are matched exactly.
+#[derive(Debug)]
+pub struct Choice<'a, T: 'a> {
+    target: &'a mut T,
+    variants: Vec<(String, T)>,
+}
+
+impl<'a, T: Clone> Choice<'a, T> {
+    /// Creates a target for the variable, with the values for each name.
+    pub fn new(target: &'a mut T, variants: &[(&str, T)]) -> Choice<'a, T> {
+        let variants = variants.iter().map(|&(name, ref value)| (name.to_string(), value.clone())).collect();
+        Choice { target, variants }
+    }
+    
+    /// Returns the names that can be given, in order.
+    pub fn names(&self) -> Vec<&str> {
+        self.variants.iter().map(|(name, _)| name.as_str()).collect()
+    }
+}
+
+impl<'a, T> SingleTarget for Choice<'a, T> where T: Debug + Clone {
+    fn parse(&mut self, value: &str) -> Result<(), String> {
+        match self.variants.iter().find(|&(name, _)| name == value) {
+            Some((_, variant)) => {
+                *self.target = variant.clone();
+                Ok(())
+            }
+            None => Err(format!("Invalid value '{}' (expected one of: {})", value, self.names().join(", "))),
+        }
+    }
+    
+    fn check(&self, value: &str) -> Result<(), String> {
+        if self.variants.iter().any(|(name, _)| name == value) {
+            Ok(())
+        } else {
+            Err(format!("Invalid value '{}' (expected one of: {})", value, self.names().join(", ")))
+        }
+    }
+}
+
+/// A map that keeps its entries in the order they were given, as a target
+/// for `key=value` pairs (like the other maps).
+///
+/// By default a repeated key replaces the earlier value, but keeps the 
+/// position where the key was first given. A map made with `multi` instead
+/// keeps every pair, so a key can have several values.
+///
+/// # Example
+/// ```
+/// # use argonaut::{ArgDef, OrderedMap, parse_plain};
+/// let mut vars = OrderedMap::new();
+/// parse_plain("prog", &["a=1", "b=2", "a=3"], vec![
+///     ArgDef::trail("vars", true, &mut vars),
+/// ]).unwrap();
+/// assert_eq!(vars.entries(), &[("a".to_string(), 3), ("b".to_string(), 2)]);
+///
+/// let mut vars: OrderedMap<String, u32> = OrderedMap::multi();
+/// parse_plain("prog", &["a=1", "b=2", "a=3"], vec![
+///     ArgDef::trail("vars", true, &mut vars),
+/// ]).unwrap();
+/// assert_eq!(vars.get("a"), Some(&3));
+/// assert_eq!(vars.get_all("a"), vec![&1, &3]);
+/// assert_eq!(vars.len(), 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderedMap<K, V> {
+    entries: Vec<(K, V)>,
+    multi: bool,
+}
+
+impl<K: PartialEq, V> OrderedMap<K, V> {
+    /// Creates an empty map where a repeated key replaces the earlier value.
+    pub fn new() -> OrderedMap<K, V> {
+        OrderedMap { entries: Vec::new(), multi: false }
+    }
+    
+    /// Creates an empty map that keeps every value of a repeated key.
+    pub fn multi() -> OrderedMap<K, V> {
+        OrderedMap { entries: Vec::new(), multi: true }
+    }
+    
+    /// Adds a pair to the map, following its policy for repeated keys.
+    pub fn insert(&mut self, key: K, value: V) {
+        if ! self.multi {
+            if let Some(entry) = self.entries.iter_mut().find(|entry| entry.0 == key) {
+                entry.1 = value;
+                return;
+            }
+        }
+        self.entries.push((key, value));
+    }
+    
+    /// Returns the last value given for the key.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V> where K: Borrow<Q>, Q: ?Sized + PartialEq {
+        self.entries.iter().rev().find(|entry| entry.0.borrow() == key).map(|entry| &entry.1)
+    }
+    
+    /// Returns every value given for the key, in order.
+    pub fn get_all<Q>(&self, key: &Q) -> Vec<&V> where K: Borrow<Q>, Q: ?Sized + PartialEq {
+        self.entries.iter().filter(|entry| entry.0.borrow() == key).map(|entry| &entry.1).collect()
+    }
+    
+    /// Returns the pairs of the map, in order.
+    pub fn entries(&self) -> &[(K, V)] {
+        &self.entries
+    }
+    
+    /// Returns the number of pairs in the map.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    /// Returns whether the map has no pairs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl<K: PartialEq, V> Default for OrderedMap<K, V> {
+    fn default() -> OrderedMap<K, V> {
+        OrderedMap::new()
+    }
+}
+
+impl<K, V> CollectionTarget for OrderedMap<K, V> 
+  where K: Debug + FromStr + PartialEq, V: Debug + FromStr 
+{
+    fn parse_and_add(&mut self, value: &str) -> Result<(), String> {
+        match value.find('=') {
+            Some(index) => self.parse_and_add_pair(&value[..index], &value[index + 1..]),
+            None => Err(format!("Expected a 'key=value' pair, not '{}'", value)),
+        }
+    }
+    
+    fn parse_and_add_pair(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let (key, value) = parse_pair(key, value)?;
+        self.insert(key, value);
         Ok(())
     }
 }
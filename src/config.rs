@@ -0,0 +1,660 @@
+use summary::ParseSummary;
+use std::collections::HashMap;
+use argdef::ArgDef;
+use help::Help;
+use report::{ErrorReporter, PrintReporter};
+
+/// The hook that is run when a parse succeeds (see `ParseConfig::after`).
+pub(crate) type AfterHook<'def> = Box<FnMut(&ParseSummary<'def>) + 'def>;
+
+/// Settings that change how a parse behaves.
+///
+/// The default configuration gives the same behavior as `parse` and
+/// `parse_plain`.
+pub struct ParseConfig<'def> {
+    pub(crate) after: Option<AfterHook<'def>>,
+    pub(crate) negative_numbers: bool,
+    pub(crate) leading_dash_digit_values: bool,
+    pub(crate) reporter: Box<ErrorReporter + 'def>,
+    pub(crate) defaults: HashMap<String, String>,
+    pub(crate) catch_interrupt_panics: bool,
+    pub(crate) plus_toggles: bool,
+    pub(crate) prefixes: Prefixes,
+    pub(crate) record_spellings: bool,
+    pub(crate) positionals_first: bool,
+    pub(crate) deferred_parsing: bool,
+    pub(crate) unknown_long_to_trail: bool,
+    pub(crate) max_args: Option<usize>,
+    pub(crate) max_positionals: Option<usize>,
+    pub(crate) suggestions: (usize, usize),
+    pub(crate) abbreviations: bool,
+    pub(crate) ignore_option_case: bool,
+    pub(crate) warnings: Box<FnMut(&str) + 'def>,
+    pub(crate) empty_value_warnings: bool,
+    pub(crate) dry_run: bool,
+    pub(crate) error_header: String,
+    pub(crate) help_hint: bool,
+}
+
+/// The strings that start short and long options.
+#[derive(Debug, Clone)]
+pub(crate) struct Prefixes {
+    pub(crate) short: String,
+    pub(crate) long: String,
+}
+
+impl Default for Prefixes {
+    fn default() -> Prefixes {
+        Prefixes { short: "-".to_string(), long: "--".to_string() }
+    }
+}
+
+impl Prefixes {
+    /// Splits the prefix from an option, and tells whether it is long.
+    ///
+    /// When both prefixes match (like `-` and `--`), the longer one is used.
+    pub(crate) fn split<'a>(&self, arg: &'a str) -> Option<(bool, &'a str)> {
+        let long = arg.starts_with(self.long.as_str());
+        let short = arg.starts_with(self.short.as_str());
+        if long && (! short || self.long.len() >= self.short.len()) {
+            Some((true, &arg[self.long.len()..]))
+        } else if short {
+            Some((false, &arg[self.short.len()..]))
+        } else {
+            None
+        }
+    }
+
+    /// Splits a value attached with `=` from a long option (`--opt=value`).
+    pub(crate) fn split_attached<'a>(&self, option: &'a str) -> (&'a str, Option<&'a str>) {
+        if let Some((true, _)) = self.split(option) {
+            if let Some(index) = option.find('=') {
+                return (&option[..index], Some(&option[index + 1..]));
+            }
+        }
+        (option, None)
+    }
+}
+
+impl<'def> ParseConfig<'def> {
+    /// Creates the default configuration.
+    pub fn new() -> ParseConfig<'def> {
+        ParseConfig {
+            after: None,
+            negative_numbers: false,
+            leading_dash_digit_values: false,
+            reporter: Box::new(PrintReporter),
+            defaults: HashMap::new(),
+            catch_interrupt_panics: false,
+            plus_toggles: false,
+            prefixes: Prefixes::default(),
+            record_spellings: false,
+            positionals_first: false,
+            deferred_parsing: false,
+            unknown_long_to_trail: false,
+            max_args: None,
+            max_positionals: None,
+            suggestions: (2, 3),
+            abbreviations: false,
+            ignore_option_case: false,
+            warnings: Box::new(|warning| eprintln!("WARNING: {}", warning)),
+            empty_value_warnings: true,
+            dry_run: false,
+            error_header: "Parse failed: ".to_string(),
+            help_hint: false,
+        }
+    }
+
+    /// Registers a hook that is run when the parse succeeds, right before the
+    /// summary is returned.
+    ///
+    /// The hook is NOT run when the parse fails or is interrupted (eg. by
+    /// `--help`), so it is a good place for post-parse setup like normalizing
+    /// paths or deriving computed values.
+    pub fn after<F>(mut self, hook: F) -> Self
+      where F: FnMut(&ParseSummary<'def>) + 'def
+    {
+        self.after = Some(Box::new(hook));
+        self
+    }
+
+    /// Sets whether negative numbers like `-3` or `-2.5` can be given as 
+    /// positional and trail values. This is disabled by default.
+    ///
+    /// When enabled, a negative number is still read as an option if a short
+    /// identifier with that name has been defined (`-3` for `.short("3")`),
+    /// and is only read as a value otherwise. A warning is given for such
+    /// shorts, when positional arguments are also defined.
+    ///
+    /// Negative numbers always start with `-`, so they can only be mistaken 
+    /// for options when `-` is the short prefix (see `prefixes`). With other
+    /// prefixes they are plain values, and shorts like `3` don't clash.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain_with, ParseConfig};
+    /// let mut numbers: Vec<i32> = Vec::new();
+    /// parse_plain_with("prog", &["-1", "-2", "3"], vec![
+    ///     ArgDef::trail("numbers", true, &mut numbers),
+    /// ], ParseConfig::new().negative_numbers(true)).unwrap();
+    /// assert_eq!(vec![-1, -2, 3], numbers);
+    ///
+    /// let (mut numbers, mut five): (Vec<i32>, bool) = (Vec::new(), false);
+    /// parse_plain_with("prog", &["-1", "-5"], vec![
+    ///     ArgDef::trail("numbers", true, &mut numbers),
+    ///     ArgDef::flag("five", &mut five).short("5"),
+    /// ], ParseConfig::new().negative_numbers(true).warnings(|_| {})).unwrap();
+    /// assert_eq!((vec![-1], true), (numbers, five));
+    ///
+    /// let (mut numbers, mut three, mut warned): (Vec<i32>, bool, Vec<String>) = (Vec::new(), false, Vec::new());
+    /// parse_plain_with("prog", &["-3", ":3", "-2"], vec![
+    ///     ArgDef::trail("numbers", true, &mut numbers),
+    ///     ArgDef::flag("three", &mut three).short("3"),
+    /// ], ParseConfig::new().prefixes(":", "::").negative_numbers(true)
+    ///     .warnings(|warning| warned.push(warning.to_string()))).unwrap();
+    /// assert_eq!((vec![-3, -2], true), (numbers, three));
+    /// assert!(warned.is_empty());
+    /// ```
+    pub fn negative_numbers(mut self, enabled: bool) -> Self {
+        self.negative_numbers = enabled;
+        self
+    }
+
+    /// Sets whether every argument that starts with a dash and a digit (like 
+    /// `-123`, `-1.5` or `-2x`) is a positional or trail value. This is 
+    /// disabled by default.
+    ///
+    /// When enabled, such arguments are never options, even when a short 
+    /// identifier with that name is defined (a warning is given for those). 
+    /// When disabled, `-123` is looked up as the short option `123` as usual,
+    /// unless `negative_numbers` is enabled and no such short is defined. 
+    /// Option values, like in `--offset -3`, are taken as they are either way.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain_with, ParseConfig};
+    /// let mut numbers: Vec<i32> = Vec::new();
+    /// let result = parse_plain_with("prog", &["1", "-2"], vec![
+    ///     ArgDef::trail("numbers", true, &mut numbers),
+    /// ], ParseConfig::new());
+    /// assert!(result.is_err());
+    ///
+    /// let mut numbers: Vec<i32> = Vec::new();
+    /// let result = parse_plain_with("prog", &["1", "-2"], vec![
+    ///     ArgDef::trail("numbers", true, &mut numbers),
+    /// ], ParseConfig::new().treat_leading_dash_digit_as_value(true));
+    /// assert!(result.is_ok());
+    /// assert_eq!(numbers, vec![1, -2]);
+    /// ```
+    pub fn treat_leading_dash_digit_as_value(mut self, enabled: bool) -> Self {
+        self.leading_dash_digit_values = enabled;
+        self
+    }
+    
+    /// Sets how `parse_with` reports failed parses to the user.
+    ///
+    /// By default the error and a usage message are printed to stdout
+    /// (see `PrintReporter`).
+    pub fn reporter<R>(mut self, reporter: R) -> Self
+      where R: ErrorReporter + 'def
+    {
+        self.reporter = Box::new(reporter);
+        self
+    }
+
+    /// Adds default values for options, by their (long) names.
+    ///
+    /// If an option isn't given in the arguments, its default is parsed as if
+    /// it had been: settings and collectors parse it as their value, flags 
+    /// parse it as a `bool` and counts as a number. Defaults for unknown
+    /// options make the parse fail.
+    pub fn defaults<I, K, V>(mut self, defaults: I) -> Self
+      where I: IntoIterator<Item=(K, V)>, K: Into<String>, V: Into<String>
+    {
+        self.defaults.extend(defaults.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Sets whether a panic in an interrupt callback should fail the parse 
+    /// (with a `ParseFailed` error), instead of unwinding through it.
+    ///
+    /// This is disabled by default. It is useful when the callbacks come 
+    /// from code that can't be trusted not to panic, like plugins.
+    ///
+    /// The failure has the kind `FailureKind::InterruptPanicked` and names the
+    /// interrupt as it was given. Its message includes the panic message, 
+    /// when the panic was given a string.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, ParseConfig, ParseError, FailureKind, parse_plain_with};
+    /// # use std::panic;
+    /// # panic::set_hook(Box::new(|_| {}));
+    /// let result = parse_plain_with("prog", &["--doctor"], vec![
+    ///     ArgDef::interrupt("doctor", |_| panic!("no patients")),
+    /// ], ParseConfig::new().catch_interrupt_panics(true));
+    /// match result {
+    ///     Err(ParseError::ParseFailed(failure, _)) => {
+    ///         assert_eq!(FailureKind::InterruptPanicked, failure.kind);
+    ///         assert_eq!(Some("--doctor".to_string()), failure.option);
+    ///         assert_eq!("The interrupt handler for '--doctor' failed: no patients", failure.message);
+    ///     }
+    ///     other => panic!("Unexpected result: {:?}", other),
+    /// }
+    /// ```
+    pub fn catch_interrupt_panics(mut self, enabled: bool) -> Self {
+        self.catch_interrupt_panics = enabled;
+        self
+    }
+
+    /// Sets whether flags are toggled like shell options (`set +x`).
+    ///
+    /// When enabled, `+name` sets a flag to `true` and `-name`/`--name` sets
+    /// it to `false`. The name after a `+` is looked up as a long name first,
+    /// and as a short identifier if no long name matches. Only flags can be 
+    /// given with a `+`; other options still use dashes as usual.
+    ///
+    /// This is disabled by default.
+    pub fn plus_toggles(mut self, enabled: bool) -> Self {
+        self.plus_toggles = enabled;
+        self
+    }
+
+    /// Sets the strings that start short and long options, instead of `-` 
+    /// and `--`.
+    ///
+    /// The long prefix on its own is then used to end the options, instead of
+    /// `--`. With `prefixes(":", "::")`, the arguments `::name=value :n ::`
+    /// are read like `--name=value -n --` normally would be. The prefixes must
+    /// be non-empty and different, or the definitions are invalid. Help and 
+    /// usage messages show the options with the same prefixes.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, Help, parse_with, parse_plain_with, ParseConfig, ParseError, ErrorReporter, ParseFailure};
+    /// let (mut name, mut verbose, mut files) = (None::<String>, false, Vec::<String>::new());
+    /// parse_plain_with("prog", &["::name=x", ":v", "::", ":file"], vec![
+    ///     ArgDef::setting("name", &mut name),
+    ///     ArgDef::flag("verbose", &mut verbose).short("v"),
+    ///     ArgDef::trail("files", true, &mut files),
+    /// ], ParseConfig::new().prefixes(":", "::")).unwrap();
+    /// assert_eq!(Some("x".to_string()), name);
+    /// assert!(verbose);
+    /// assert_eq!(vec![":file".to_string()], files);
+    ///
+    /// // The dashes are no longer prefixes, so this is a trail value.
+    /// let mut rest = Vec::<String>::new();
+    /// parse_plain_with("prog", &["--name=y"], vec![
+    ///     ArgDef::setting("name", &mut name),
+    ///     ArgDef::trail("files", true, &mut rest),
+    /// ], ParseConfig::new().prefixes(":", "::")).unwrap();
+    /// assert_eq!(Some("x".to_string()), name);
+    /// assert_eq!(vec!["--name=y".to_string()], rest);
+    ///
+    /// let interrupted = parse_plain_with("prog", &[":d"], vec![
+    ///     ArgDef::interrupt("doctor", |_| {}).short("d"),
+    /// ], ParseConfig::new().prefixes(":", "::")).unwrap_err();
+    /// assert_eq!("Interrupted by '::doctor'", interrupted.to_string());
+    /// assert!(interrupted.to_json().contains(r#""option":"::doctor""#));
+    ///
+    /// match parse_plain_with("prog", &["x"], vec![
+    ///     ArgDef::trail("files", true, &mut files),
+    /// ], ParseConfig::new().prefixes(":", ":")) {
+    ///     Err(ParseError::InvalidDefinitions(_)) => {}
+    ///     _ => panic!("The prefixes should be invalid"),
+    /// }
+    ///
+    /// struct Usage;
+    /// impl ErrorReporter for Usage {
+    ///     fn report(&mut self, _failure: &ParseFailure, help: &Help) {
+    ///         assert_eq!("prog [:h, OPTS...] [::] [files...]", help.usage_message());
+    ///         let message = help.help_message("");
+    ///         assert!(message.contains("  ::port, :p PORT\n"));
+    ///         assert!(message.contains("  ::help, :h ( X )\n"));
+    ///         assert!(help.compact_help().contains("Options: :p/::port, :h/::help\n"));
+    ///         assert!(help.markdown("").contains("- `::port`, `:p` `PORT`"));
+    ///     }
+    /// }
+    /// let result = parse_with("prog", &["::what"], vec![
+    ///     ArgDef::setting("port", &mut None::<u16>).short("p"),
+    ///     ArgDef::default_help("").short("h"),
+    ///     ArgDef::trail("files", true, &mut files),
+    /// ], ParseConfig::new().prefixes(":", "::").reporter(Usage));
+    /// assert!(result.is_err());
+    /// ```
+    pub fn prefixes<S, L>(mut self, short: S, long: L) -> Self 
+      where S: Into<String>, L: Into<String>
+    {
+        self.prefixes = Prefixes { short: short.into(), long: long.into() };
+        self
+    }
+
+    /// Sets whether the summary should record how each option was spelled in
+    /// the arguments (see `ParseSummary::spellings`). This is disabled by
+    /// default.
+    pub fn record_spellings(mut self, enabled: bool) -> Self {
+        self.record_spellings = enabled;
+        self
+    }
+
+    /// Sets whether all positional arguments must be given before any option.
+    /// This is disabled by default.
+    ///
+    /// When enabled, an option given while positionals are still missing 
+    /// fails the parse, so `prog FILE --verbose` works but 
+    /// `prog --verbose FILE` doesn't. Interrupts like `--help` can still be 
+    /// given anywhere.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, ParseConfig, parse_plain_with};
+    /// let (mut verbose, mut file) = (false, String::new());
+    /// parse_plain_with("prog", &["file", "-v"], vec![
+    ///     ArgDef::flag("verbose", &mut verbose).short("v"),
+    ///     ArgDef::positional("file", &mut file),
+    /// ], ParseConfig::new().positionals_first(true)).unwrap();
+    /// assert!(verbose);
+    ///
+    /// let (mut verbose, mut file) = (false, String::new());
+    /// let result = parse_plain_with("prog", &["-v", "file"], vec![
+    ///     ArgDef::flag("verbose", &mut verbose).short("v"),
+    ///     ArgDef::positional("file", &mut file),
+    /// ], ParseConfig::new().positionals_first(true));
+    /// assert!(result.is_err());
+    /// ```
+    pub fn positionals_first(mut self, enabled: bool) -> Self {
+        self.positionals_first = enabled;
+        self
+    }
+
+    /// Sets whether values are only parsed into their targets once the 
+    /// arguments as a whole are known to be valid. This is disabled by default.
+    ///
+    /// When enabled, the values of positionals, trails, settings and 
+    /// collectors are kept as they are given, and parsed at the end (in the
+    /// same order), after missing arguments, requirements and conflicts have
+    /// been checked. This avoids expensive parses (like compiling a regex) 
+    /// for arguments that fail anyway, but also means that a bad value is only
+    /// reported when everything else is right. The targets of an interrupted
+    /// parse get none of the deferred values. Defaults are not deferred.
+    pub fn deferred_parsing(mut self, enabled: bool) -> Self {
+        self.deferred_parsing = enabled;
+        self
+    }
+
+    /// Sets whether unknown long options are added to the trail as they are,
+    /// instead of failing the parse. This is disabled by default.
+    ///
+    /// This lets a wrapper of another command read the options it knows, and
+    /// pass everything else on: with a trail `args`, `--known --other=1` 
+    /// leaves `["--other=1"]` in `args`. Only the option itself is added; the
+    /// argument after it is read as usual, so `--other 1` gives `1` to the 
+    /// next positional if there is one (and to the trail otherwise). Unknown
+    /// short options still fail the parse, as do unknown long options when no 
+    /// trail is defined.
+    pub fn unknown_long_to_trail(mut self, enabled: bool) -> Self {
+        self.unknown_long_to_trail = enabled;
+        self
+    }
+
+    /// Sets the most arguments a parse accepts. There is no limit by default.
+    ///
+    /// More arguments fail the parse before any of them is read, so nothing
+    /// is parsed into the targets. This guards programs that parse arguments
+    /// from untrusted input against collectors and trails growing without 
+    /// bound. Every argument counts, including option values and `--`.
+    pub fn max_args(mut self, max: usize) -> Self {
+        self.max_args = Some(max);
+        self
+    }
+
+    /// Sets the text that is printed before the message of a failed parse,
+    /// instead of `Parse failed: `.
+    ///
+    /// This brands the errors of a tool, like `error: ` for cargo-style 
+    /// messages, or `prog: ` to name the program. It is given to reporters
+    /// as `Help::error_header`, which `PrintReporter` prints as it is, so the
+    /// header should end with the separator it needs.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_with, ParseConfig, ParseFailure, Help, ErrorReporter};
+    /// struct Check;
+    /// impl ErrorReporter for Check {
+    ///     fn report(&mut self, failure: &ParseFailure, help: &Help) {
+    ///         let line = format!("{}{}", help.error_header, failure);
+    ///         assert_eq!("error: Unknown option '--what'", line);
+    ///     }
+    /// }
+    ///
+    /// let result = parse_with("prog", &["--what"], vec![
+    ///     ArgDef::flag("verbose", &mut false),
+    /// ], ParseConfig::new().error_header("error: ").reporter(Check));
+    /// assert!(result.is_err());
+    /// ```
+    pub fn error_header<S>(mut self, header: S) -> Self where S: Into<String> {
+        self.error_header = header.into();
+        self
+    }
+    
+    /// Sets whether failed parses end with a short hint to the help, like
+    /// `For more information, try '-h' or '--help'.`, instead of the usage 
+    /// message.
+    ///
+    /// This keeps errors short, cargo-style, while still pointing to the 
+    /// details. The hint is only shown when `--help` is defined, so the usage
+    /// message is still printed otherwise. It is given to reporters as 
+    /// `Help::help_hint`. The default is false, which prints the usage.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_with, ParseConfig, ParseFailure, Help, ErrorReporter};
+    /// struct Last(String);
+    /// impl ErrorReporter for Last {
+    ///     // Like `PrintReporter`, but checks the last line.
+    ///     fn report(&mut self, _failure: &ParseFailure, help: &Help) {
+    ///         let last = match help.hint_message() {
+    ///             Some(hint) if help.help_hint => hint,
+    ///             _ => format!("Usage: {}", help.usage_message()),
+    ///         };
+    ///         assert_eq!(self.0, last);
+    ///     }
+    /// }
+    ///
+    /// let hint = "For more information, try '-h' or '--help'.";
+    /// let config = ParseConfig::new().help_hint(true).reporter(Last(hint.to_string()));
+    /// let result = parse_with("prog", &["--what"], vec![
+    ///     ArgDef::default_help("").short("h"),
+    /// ], config);
+    /// assert!(result.is_err());
+    ///
+    /// let hint = "For more information, try '/h' or '//help'.";
+    /// let config = ParseConfig::new().help_hint(true).prefixes("/", "//")
+    ///     .reporter(Last(hint.to_string()));
+    /// let result = parse_with("prog", &["//what"], vec![
+    ///     ArgDef::default_help("").short("h"),
+    /// ], config);
+    /// assert!(result.is_err());
+    ///
+    /// let config = ParseConfig::new().reporter(Last("Usage: prog [-h]".to_string()));
+    /// let result = parse_with("prog", &["--what"], vec![
+    ///     ArgDef::default_help("").short("h"),
+    /// ], config);
+    /// assert!(result.is_err());
+    /// ```
+    pub fn help_hint(mut self, enabled: bool) -> Self {
+        self.help_hint = enabled;
+        self
+    }
+    
+    /// Sets the most positional values a parse accepts, counting both the
+    /// positional arguments and the values of the trail. There is no limit
+    /// by default, besides the bounds of the trail.
+    ///
+    /// An extra value fails the parse with a `TooManyArguments` failure that
+    /// names the limit. Values split from a trail argument by its delimiter
+    /// count as one, as do the arguments passed on after a command. The
+    /// definitions are invalid if they need more values than this.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain_with, ParseConfig};
+    /// let (mut input, mut extra): (String, Vec<String>) = (String::new(), Vec::new());
+    /// let result = parse_plain_with("prog", &["in.txt", "a", "b", "c"], vec![
+    ///     ArgDef::positional("input", &mut input),
+    ///     ArgDef::trail("extra", true, &mut extra),
+    /// ], ParseConfig::new().max_positionals(3));
+    /// assert_eq!("Too many positional arguments (max 3): 'c'", result.unwrap_err().to_string());
+    /// ```
+    pub fn max_positionals(mut self, max: usize) -> Self {
+        self.max_positionals = Some(max);
+        self
+    }
+    
+    /// Sets how unknown long options suggest the defined ones they may be a 
+    /// typo of, like `Unknown option '--verbsoe' (did you mean '--verbose'?)`.
+    ///
+    /// Options are suggested when their names are at most `max_distance` 
+    /// edits (insertions, deletions or substitutions) away from the given 
+    /// one, closest first, and at most `max_count` of them are shown. The 
+    /// default is a distance of 2 and up to 3 suggestions; a count of 0 turns
+    /// the suggestions off.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain_with, ParseConfig};
+    /// fn error(config: ParseConfig) -> String {
+    ///     let (mut verbose, mut version) = (false, false);
+    ///     parse_plain_with("prog", &["--versoin"], vec![
+    ///         ArgDef::flag("verbose", &mut verbose),
+    ///         ArgDef::flag("version", &mut version),
+    ///     ], config).unwrap_err().to_string()
+    /// }
+    /// assert_eq!("Unknown option '--versoin' (did you mean '--version'?)", 
+    ///            error(ParseConfig::new()));
+    /// assert_eq!("Unknown option '--versoin' (did you mean '--version', '--verbose'?)", 
+    ///            error(ParseConfig::new().suggestions(3, 3)));
+    /// assert_eq!("Unknown option '--versoin' (did you mean '--version'?)", 
+    ///            error(ParseConfig::new().suggestions(3, 1)));
+    /// assert_eq!("Unknown option '--versoin'", error(ParseConfig::new().suggestions(1, 3)));
+    /// ```
+    pub fn suggestions(mut self, max_distance: usize, max_count: usize) -> Self {
+        self.suggestions = (max_distance, max_count);
+        self
+    }
+    
+    /// Sets whether long options can be given by any unambiguous prefix of
+    /// their names, so `--verb` is read as `--verbose` when no other option
+    /// starts with `verb`. A name that is defined is always matched exactly,
+    /// and a prefix of several names fails the parse, like
+    /// `Ambiguous option '--in' (include, info)`. This also applies to the
+    /// `no-` negations of features.
+    ///
+    /// The default is false, so only the full names are accepted.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain_with, ParseConfig};
+    /// let (mut verbose, mut include, mut info) = (false, None::<String>, false);
+    /// parse_plain_with("prog", &["--verb", "--inc=x"], vec![
+    ///     ArgDef::flag("verbose", &mut verbose),
+    ///     ArgDef::setting("include", &mut include),
+    ///     ArgDef::flag("info", &mut info),
+    /// ], ParseConfig::new().abbreviations(true)).unwrap();
+    /// assert!(verbose);
+    /// assert_eq!(Some("x".to_string()), include);
+    ///
+    /// let error = parse_plain_with("prog", &["--in"], vec![
+    ///     ArgDef::setting("include", &mut include),
+    ///     ArgDef::flag("info", &mut info),
+    /// ], ParseConfig::new().abbreviations(true)).unwrap_err();
+    /// assert_eq!("Ambiguous option '--in' (include, info)", error.to_string());
+    /// ```
+    pub fn abbreviations(mut self, enabled: bool) -> Self {
+        self.abbreviations = enabled;
+        self
+    }
+    
+    /// Sets whether long options are matched regardless of case, so 
+    /// `--Verbose` and `--VERBOSE` are both read as `--verbose`. Short
+    /// options are still case-sensitive, since `-v` and `-V` often mean
+    /// different things.
+    ///
+    /// With this on, defining two long options (or `no-` negations) that 
+    /// only differ by case makes the definitions invalid. The default is 
+    /// false.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_plain_with, ParseConfig, ParseError};
+    /// let (mut verbose, mut version) = (false, false);
+    /// parse_plain_with("prog", &["--VERBOSE", "-V"], vec![
+    ///     ArgDef::flag("verbose", &mut verbose).short("v"),
+    ///     ArgDef::flag("version", &mut version).short("V"),
+    /// ], ParseConfig::new().ignore_option_case(true)).unwrap();
+    /// assert!(verbose && version);
+    ///
+    /// let (mut lower, mut upper) = (false, false);
+    /// match parse_plain_with("prog", &["--dry-run"], vec![
+    ///     ArgDef::flag("dry-run", &mut lower),
+    ///     ArgDef::flag("Dry-Run", &mut upper),
+    /// ], ParseConfig::new().ignore_option_case(true)) {
+    ///     Err(ParseError::InvalidDefinitions(_)) => {}
+    ///     _ => panic!("The options should collide"),
+    /// }
+    /// ```
+    pub fn ignore_option_case(mut self, enabled: bool) -> Self {
+        self.ignore_option_case = enabled;
+        self
+    }
+    
+    /// Sets where warnings about the definitions go, like a builder that 
+    /// doesn't apply to the kind of argument it is used on.
+    ///
+    /// By default each warning is printed to stderr. The sink can instead
+    /// collect them, log them or ignore them. Warnings are reported when the
+    /// definitions are parsed, before any argument is read, except for the
+    /// warnings about likely mistakes in the arguments 
+    /// (see `empty_value_warnings`).
+    pub fn warnings<F>(mut self, sink: F) -> Self 
+      where F: FnMut(&str) + 'def
+    {
+        self.warnings = Box::new(sink);
+        self
+    }
+
+    /// Sets whether a warning is given when a setting gets an empty attached
+    /// value and a plain argument follows it, like `--output= out.txt`. This
+    /// is enabled by default.
+    ///
+    /// That is usually a quoting mistake, where `out.txt` was meant as the 
+    /// value. The parse still goes on as the arguments say, with an empty 
+    /// value, so the warning only goes to the `warnings` sink.
+    pub fn empty_value_warnings(mut self, enabled: bool) -> Self {
+        self.empty_value_warnings = enabled;
+        self
+    }
+    
+    /// Creates the help for the definitions, with the settings of this config.
+    pub(crate) fn help<'tar>(&self, program: String, definitions: &[ArgDef<'def, 'tar>]) -> Help<'def> {
+        let mut help = Help::with_prefixes(program, definitions, self.prefixes.clone());
+        help.error_header = self.error_header.clone();
+        help.help_hint = self.help_hint;
+        help.ignore_case = self.ignore_option_case;
+        help
+    }
+    
+    /// Runs the `after` hook (if any) and passes the summary on.
+    pub(crate) fn succeed<E>(&mut self, summary: ParseSummary<'def>) -> Result<ParseSummary<'def>, E> {
+        if let Some(ref mut hook) = self.after {
+            hook(&summary);
+        }
+        Ok(summary)
+    }
+}
+
+impl<'def> Default for ParseConfig<'def> {
+    fn default() -> ParseConfig<'def> {
+        ParseConfig::new()
+    }
+}
@@ -1,16 +1,24 @@
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
 use argdef::{ArgDef, ArgDefKind};
-use std_unicode::str::UnicodeStr;
+use config::Prefixes;
+use layered::env_var;
+use report::json_object;
 
-pub fn trim_and_strip_lines<'a>(text: &'a str) -> impl Iterator<Item=&'a str> {
+pub fn trim_and_strip_lines(text: &str) -> impl Iterator<Item=&str> {
     let rev: Vec<_> = text.lines()
-        .rev().skip_while(|&l| l == "" || l.is_whitespace()).collect();
+        .rev().skip_while(|&l| l.trim().is_empty()).collect();
     rev.into_iter()
-        .rev().skip_while(|&l| l == "" || l.is_whitespace())
+        .rev().skip_while(|&l| l.trim().is_empty())
         .map(|line| line.trim())
 }
 
-fn write_trimmed_n<'def, T: AsRef<str>>(s: &mut String, prefix: &str, text: T) {
+fn write_trimmed_n<T: AsRef<str>>(s: &mut String, prefix: &str, text: T) {
     for line in trim_and_strip_lines(text.as_ref()) {
         s.push_str(prefix);
         s.push_str(line);
@@ -18,6 +26,70 @@ fn write_trimmed_n<'def, T: AsRef<str>>(s: &mut String, prefix: &str, text: T) {
     }
 }
 
+/// Writes a Markdown list item for an argument, with its help on one line.
+fn write_markdown_item<'def>(s: &mut String, item: &str, help: &Option<Cow<'def, str>>) {
+    s.push_str("- ");
+    s.push_str(item);
+    if let Some(ref help) = *help {
+        let text = trim_and_strip_lines(help).filter(|line| ! line.is_empty())
+            .collect::<Vec<_>>().join(" ");
+        s.push_str(": ");
+        s.push_str(&text);
+    }
+    s.push('\n');
+}
+
+/// Separates a new help section from the text before it with exactly one
+/// empty line.
+fn start_section(s: &mut String) {
+    while s.ends_with("\n\n") {
+        s.pop();
+    }
+    s.push('\n');
+}
+
+/// Returns the number of lines of the terminal: `$LINES` when it is exported,
+/// otherwise what `stty size` reports for the controlling terminal, or 24 
+/// when neither is known.
+fn terminal_height() -> usize {
+    if let Some(lines) = env::var("LINES").ok().and_then(|lines| lines.parse().ok()) {
+        return lines;
+    }
+    File::open("/dev/tty").ok()
+        .and_then(|tty| Command::new("stty").arg("size").stdin(tty).stderr(Stdio::null()).output().ok())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .and_then(|size| size.split_whitespace().next().and_then(|rows| rows.parse().ok()))
+        .unwrap_or(24)
+}
+
+/// Shows the text in a pager if it doesn't fit in the terminal.
+/// Returns whether the text was shown.
+fn page(text: &str) -> bool {
+    if ! io::stdout().is_terminal() {
+        return false;
+    }
+    if text.lines().count() < terminal_height() {
+        return false;
+    }
+    let pager = env::var("PAGER").unwrap_or_else(|_| String::from("less"));
+    let mut words = pager.split_whitespace();
+    let program = match words.next() {
+        Some(program) => program,
+        None => return false,
+    };
+    let mut child = match Command::new(program).args(words).stdin(Stdio::piped()).spawn() {
+        Ok(child) => child,
+        Err(_) => return false,
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager may be closed before it has read everything.
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait().is_ok()
+}
+
+/// The (name, short, kind, help) of an option in the help.
+type HelpOption<'def> = (Cow<'def, str>, Option<Cow<'def, str>>, HelpOptKind<'def>, Option<Cow<'def, str>>);
 
 /// A collection of descriptions of the defined arguments.
 #[derive(Debug)]
@@ -28,55 +100,142 @@ pub struct Help<'def> {
     pub positional: Vec<(Cow<'def, str>, Option<Cow<'def, str>>)>,
     /// Trailing positional vararg.
     pub trail: Option<(Cow<'def, str>, bool, Option<Cow<'def, str>>)>,
+    /// The (min, max) number of trail values, if the trail has bounds.
+    pub trail_bounds: Option<(usize, Option<usize>)>,
     /// Subcommand arguments.
     pub subcommands: Vec<(Cow<'def, str>, Option<Cow<'def, str>>)>,
-    /// Optional arguments (name, short, kind, help).
+    /// Optional arguments (name, short, kind, help), in definition order.
+    // Spelled out, as the alias is private.
+    #[allow(clippy::type_complexity)]
     pub options: Vec<(Cow<'def, str>, Option<Cow<'def, str>>, HelpOptKind<'def>, Option<Cow<'def, str>>)>,
     /// Is `--help` defined.
     pub help_defined: bool,
+    /// The names of the options that are only shown in advanced help.
+    pub hidden: HashSet<Cow<'def, str>>,
+    /// The names of the options that must be given.
+    pub required: HashSet<Cow<'def, str>>,
+    /// The types of the values that arguments take, by argument name.
+    pub value_types: HashMap<Cow<'def, str>, Cow<'def, str>>,
+    /// The long help descriptions of arguments, by argument name. The other
+    /// fields have the short descriptions.
+    pub long_help: HashMap<Cow<'def, str>, Cow<'def, str>>,
+    /// The help of nested subcommands, by subcommand name (see `nest`).
+    pub nested: HashMap<Cow<'def, str>, Help<'def>>,
+    /// The prefix of the environment variables that options are read from,
+    /// if any (see `environment`).
+    pub env_prefix: Option<String>,
+    /// The environment variables that options are read from, by option name
+    /// (see `ArgDef::env`). These are listed even without a prefix.
+    pub env_vars: HashMap<Cow<'def, str>, Cow<'def, str>>,
+    /// The text that reporters put before the message of a failed parse
+    /// (see `ParseConfig::error_header`).
+    pub error_header: String,
+    /// Whether reporters end the message of a failed parse with a hint to 
+    /// `--help` instead of the usage message (see `ParseConfig::help_hint`).
+    pub help_hint: bool,
+    /// The default values of settings, by setting name (see 
+    /// `ArgDef::default`).
+    pub defaults: HashMap<Cow<'def, str>, Cow<'def, str>>,
+    /// The strings that start short and long options (see 
+    /// `ParseConfig::prefixes`).
+    pub(crate) prefixes: Prefixes,
+    /// Whether long options are matched regardless of case (see 
+    /// `ParseConfig::ignore_option_case`).
+    pub(crate) ignore_case: bool,
 }
 
 impl<'def> Help<'def> {
     /// Creates a new help object from the given descriptions.
     pub fn new<'tar>(program: String, definitions: &[ArgDef<'def, 'tar>]) -> Help<'def> {
+        Help::with_prefixes(program, definitions, Prefixes::default())
+    }
+    
+    /// Creates a new help object that shows the options with the given 
+    /// prefixes.
+    pub(crate) fn with_prefixes<'tar>(program: String, definitions: &[ArgDef<'def, 'tar>], 
+            prefixes: Prefixes) -> Help<'def> {
         let mut positional = Vec::new();
         let mut trail = None;
+        let mut trail_bounds = None;
         let mut options = Vec::new();
         let mut subcommands = Vec::new();
         let mut help_defined = false;
+        let mut value_types = HashMap::new();
+        let mut hidden = HashSet::new();
+        let mut required = HashSet::new();
+        let mut long_help = HashMap::new();
+        let mut defaults = HashMap::new();
+        let mut env_vars = HashMap::new();
         for def in definitions {
+            if let Some(ref help_long) = def.help_long {
+                long_help.insert(def.name.clone(), help_long.clone());
+            }
+            let help_desc = def.help_desc.clone().or_else(|| def.help_long.clone());
+            if def.hidden {
+                hidden.insert(def.name.clone());
+            }
+            // A computed default satisfies the requirement.
+            if def.required && def.default_with.is_none() {
+                required.insert(def.name.clone());
+            }
+            if let Some(ref value_type) = def.value_type {
+                value_types.insert(def.name.clone(), value_type.clone());
+            }
+            if let Some(ref default) = def.default {
+                defaults.insert(def.name.clone(), default.clone());
+            }
+            if let Some(ref var) = def.env {
+                env_vars.insert(def.name.clone(), var.clone());
+            }
             match def.kind {
                 ArgDefKind::Positional { .. } => {
-                    positional.push((def.name.clone(), def.help_desc.clone()));
+                    positional.push((def.name.clone(), help_desc));
+                }
+                ArgDefKind::Command { ref rest_name, .. } => {
+                    positional.push((def.name.clone(), help_desc));
+                    let rest_help = format!("The arguments for '{}', passed on as they are.", def.name);
+                    trail = Some((rest_name.clone(), true, Some(Cow::Owned(rest_help))));
                 }
-                ArgDefKind::Trail { optional, .. } => {
-                    trail = Some((def.name.clone(), optional, def.help_desc.clone()));
+                ArgDefKind::Trail { optional, bounds, .. } => {
+                    trail = Some((def.name.clone(), optional, help_desc));
+                    trail_bounds = bounds;
                 },
                 ArgDefKind::Subcommand { .. } => {
-                    subcommands.push((def.name.clone(), def.help_desc.clone()));
+                    subcommands.push((def.name.clone(), help_desc));
                 }
                 ArgDefKind::Flag { ref short, .. } => {
                     options.push((
                         def.name.clone(), short.clone(), 
-                        HelpOptKind::Flag, def.help_desc.clone()
+                        HelpOptKind::Flag, help_desc
                     ));
                 }
                 ArgDefKind::Count { ref short, .. } => {
                     options.push((
                         def.name.clone(), short.clone(), 
-                        HelpOptKind::Count, def.help_desc.clone()
+                        HelpOptKind::Count, help_desc
                     ));
                 }
                 ArgDefKind::Setting { ref short, ref param, .. } => {
                     options.push((
                         def.name.clone(), short.clone(), 
-                        HelpOptKind::Setting(param.clone()), def.help_desc.clone()
+                        HelpOptKind::Setting(param.clone()), help_desc
+                    ));
+                }
+                ArgDefKind::Features { ref short, ref param, .. } => {
+                    options.push((
+                        def.name.clone(), short.clone(),
+                        HelpOptKind::Collect(param.clone()), help_desc
+                    ));
+                    let negation = format!("Disables what '{}{}' enables.", prefixes.long, def.name);
+                    options.push((
+                        Cow::Owned(format!("no-{}", def.name)), None,
+                        HelpOptKind::Collect(param.clone()), Some(Cow::Owned(negation))
                     ));
                 }
                 ArgDefKind::Collect { ref short, ref param, .. } => {
                     options.push((
                         def.name.clone(), short.clone(),
-                        HelpOptKind::Collect(param.clone()), def.help_desc.clone()
+                        HelpOptKind::Collect(param.clone()), help_desc
                     ));
                 }
                 ArgDefKind::Interrupt { ref short, .. } => {
@@ -85,19 +244,225 @@ impl<'def> Help<'def> {
                     }
                     options.push((
                         def.name.clone(), short.clone(), 
-                        HelpOptKind::Interrupt, def.help_desc.clone()
+                        HelpOptKind::Interrupt, help_desc
                     ));
                 }
             }
         }
-        Help { program, positional, trail, subcommands, options, help_defined }
+        Help { 
+            program, positional, trail, trail_bounds, subcommands, options, help_defined, value_types, 
+            hidden, required, long_help, nested: HashMap::new(), env_prefix: None, env_vars,
+            error_header: "Parse failed: ".to_string(), help_hint: false,
+            defaults, prefixes, ignore_case: false,
+        }
+    }
+    
+    /// Returns whether the argument is `-v` or `--verbose`, as it is given 
+    /// with the prefixes of the parse.
+    pub(crate) fn is_verbose(&self, arg: &str) -> bool {
+        match self.prefixes.split(arg) {
+            Some((false, short)) => short == "v",
+            Some((true, name)) if self.ignore_case => name.to_lowercase() == "verbose",
+            Some((true, name)) => name == "verbose",
+            None => false,
+        }
+    }
+    
+    /// Sets whether usage messages should point to `--help`, instead of 
+    /// detecting it from the definitions.
+    ///
+    /// By default this is true when an interrupt named `help` is defined, 
+    /// and false for any other kind of `--help` option.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, Help};
+    /// let mut help = false;
+    /// let mut verbose = false;
+    /// let defs = vec![
+    ///     ArgDef::flag("help", &mut help),
+    ///     ArgDef::flag("verbose", &mut verbose),
+    /// ];
+    /// let usage = Help::new("prog".into(), &defs).usage_message();
+    /// assert_eq!("prog [opts...]", usage);
+    /// let usage = Help::new("prog".into(), &defs).help_defined(true).usage_message();
+    /// assert_eq!("prog [--help, OPTS...]", usage);
+    /// ```
+    pub fn help_defined(mut self, defined: bool) -> Self {
+        self.help_defined = defined;
+        self
+    }
+    
+    /// Adds the help of a subcommand, so that `tree` can show its arguments
+    /// and nested subcommands too.
+    ///
+    /// The subcommand handlers only define their arguments when they are run,
+    /// so the nested help has to be made from the same definitions separately.
+    pub fn nest<N>(mut self, subcommand: N, help: Help<'def>) -> Self 
+      where N: Into<Cow<'def, str>>
+    {
+        self.nested.insert(subcommand.into(), help);
+        self
+    }
+    
+    /// Renders the whole tree of subcommands, indented by depth, with the
+    /// short description of each subcommand.
+    ///
+    /// Subcommands are only expanded when their help has been nested with 
+    /// `nest`, so the tree is as deep as the nested help goes.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, Help};
+    /// let remote = vec![
+    ///     ArgDef::subcommand("add", |_, _| Ok(None)).help("Adds a remote."),
+    ///     ArgDef::subcommand("remove", |_, _| Ok(None)),
+    /// ];
+    /// let top = vec![
+    ///     ArgDef::subcommand("remote", |_, _| Ok(None)).help("Manages remotes."),
+    ///     ArgDef::subcommand("commit", |_, _| Ok(None)).help("Records changes."),
+    /// ];
+    /// let help = Help::new("git".into(), &top)
+    ///     .nest("remote", Help::new("git remote".into(), &remote));
+    /// assert_eq!(help.tree(), "\
+    /// git
+    ///   remote - Manages remotes.
+    ///     add - Adds a remote.
+    ///     remove
+    ///   commit - Records changes.
+    /// ");
+    /// ```
+    pub fn tree(&self) -> String {
+        let mut s = format!("{}\n", self.program);
+        self.write_tree_into(&mut s, 1);
+        s
+    }
+    
+    fn write_tree_into(&self, s: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth);
+        for (name, help) in &self.subcommands {
+            s.push_str(&indent);
+            s.push_str(name);
+            if let Some(ref help) = *help {
+                let text = trim_and_strip_lines(help).filter(|line| ! line.is_empty())
+                    .collect::<Vec<_>>().join(" ");
+                s.push_str(" - ");
+                s.push_str(&text);
+            }
+            s.push('\n');
+            if let Some(nested) = self.nested.get(name) {
+                nested.write_tree_into(s, depth + 1);
+            }
+        }
+    }
+    
+    /// Adds an "Environment" section to help messages, listing the variable
+    /// that each option can be read from (as `Source::Env` with the prefix
+    /// reads them), and whether it is currently set.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, Help};
+    /// # use std::env;
+    /// env::set_var("DOCS_HELP_HOST", "example.com");
+    /// env::remove_var("DOCS_HELP_PORT");
+    /// let (mut host, mut port) = (None::<String>, None::<u16>);
+    /// let defs = vec![
+    ///     ArgDef::setting("host", &mut host).help("The host to connect to."),
+    ///     ArgDef::setting("port", &mut port).help("The port to connect to."),
+    /// ];
+    /// let help = Help::new("prog".into(), &defs).environment("DOCS_HELP_");
+    /// assert_eq!(help.help_message(""), "\
+    /// Usage:
+    ///   prog [opts...]
+    ///
+    /// Optional arguments:
+    ///   --host HOST
+    ///       The host to connect to.
+    ///
+    ///   --port PORT
+    ///       The port to connect to.
+    ///
+    /// Environment:
+    ///   DOCS_HELP_HOST (set)
+    ///       Used for '--host' when it isn't given.
+    ///
+    ///   DOCS_HELP_PORT
+    ///       Used for '--port' when it isn't given.
+    /// ");
+    /// ```
+    pub fn environment<P>(mut self, prefix: P) -> Self where P: Into<String> {
+        self.env_prefix = Some(prefix.into());
+        self
+    }
+    
+    /// Returns whether the option is the `no-` form of a features option.
+    fn is_negation(&self, name: &str) -> bool {
+        name.starts_with("no-") && self.options.iter().any(|(other, _, kind, _)| {
+            match *kind {
+                HelpOptKind::Collect(_) => other.as_ref() == &name[3..],
+                _ => false,
+            }
+        })
+    }
+    
+    /// Returns the environment variable that the option is read from, if any.
+    fn option_env_var(&self, name: &str) -> Option<String> {
+        match self.env_vars.get(name) {
+            Some(var) => Some(var.to_string()),
+            None => self.env_prefix.as_ref().map(|prefix| env_var(prefix, name)),
+        }
+    }
+    
+    fn write_environment_into(&self, s: &mut String, show_hidden: bool) {
+        let options = self.options.iter().filter(|&(name, _, kind, _)| {
+            ! matches!(*kind, HelpOptKind::Interrupt) && (show_hidden || ! self.hidden.contains(name))
+                && ! self.is_negation(name)
+        }).filter_map(|option| self.option_env_var(&option.0).map(|var| (&option.0, var)))
+            .collect::<Vec<_>>();
+        if options.is_empty() {
+            return;
+        }
+        start_section(s);
+        s.push_str("Environment:\n");
+        for (name, var) in options {
+            let set = if env::var_os(&var).is_some() { " (set)" } else { "" };
+            s.push_str(&format!("  {}{}\n", var, set));
+            s.push_str(&format!("      Used for '{}{}' when it isn't given.\n\n", self.prefixes.long, name));
+        }
+    }
+    
+    /// Returns the value type of the argument as a suffix, like ` (integer)`.
+    fn type_hint(&self, name: &str) -> String {
+        match self.value_types.get(name) {
+            Some(value_type) => format!(" ({})", value_type),
+            None => String::new(),
+        }
+    }
+    
+    /// Returns the long description of the argument, or else the given short
+    /// one.
+    fn full_help<'a>(&'a self, name: &str, help: &'a Option<Cow<'def, str>>) -> Option<&'a Cow<'def, str>> {
+        self.long_help.get(name).or(help.as_ref())
+    }
+    
+    /// Describes how many values the trail takes, like `[file...]`, 
+    /// `file [file...]` or, when there is a maximum, `file (2-5)`.
+    fn trail_synopsis(&self) -> Option<String> {
+        self.trail_token().map(|token| token.to_string())
+    }
+    
+    fn trail_token(&self) -> Option<UsageToken<'def>> {
+        self.trail.as_ref().map(|&(ref name, optional, _)| {
+            UsageToken::Trail { name: name.clone(), optional, bounds: self.trail_bounds }
+        })
     }
     
     fn get_help_short(&self) -> Option<Cow<'def, str>> {
         if ! self.help_defined {
             return None;
         }
-        for &(ref name, ref short, _, _) in &self.options {
+        for (name, short, _, _) in &self.options {
             if name.as_ref() == "help" {
                 return short.clone();
             }
@@ -105,65 +470,88 @@ impl<'def> Help<'def> {
         None
     }
     
-    fn write_usage_into(&self, s: &mut String) {
-        s.push_str(&self.program);
+    /// Returns the parts of the usage message, for custom renderers.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, Help, UsageToken};
+    /// let mut file = String::new();
+    /// let defs = vec![
+    ///     ArgDef::positional("file", &mut file),
+    ///     ArgDef::default_help("").short("h"),
+    /// ];
+    /// let tokens = Help::new("prog".into(), &defs).usage_tokens();
+    /// assert_eq!(tokens, vec![
+    ///     UsageToken::Program("prog".into()),
+    ///     UsageToken::Options { help: Some("-h".into()), others: false },
+    ///     UsageToken::Positional("file".into()),
+    /// ]);
+    /// ```
+    pub fn usage_tokens(&self) -> Vec<UsageToken<'def>> {
+        let mut tokens = vec![UsageToken::Program(self.program.clone())];
         
         if ! self.options.is_empty() {
-            if self.help_defined {
-                if let Some(help_short) = self.get_help_short() {
-                    s.push_str(" [-");
-                    s.push_str(help_short.as_ref());
-                    if self.options.len() > 1 {
-                        s.push_str(", OPTS...");
-                    }
-                    s.push_str("]");
-                } else {
-                    s.push_str(" [--help");
-                    if self.options.len() > 1 {
-                        s.push_str(", OPTS...");
-                    }
-                    s.push_str("]");
-                }
+            let help = if ! self.help_defined {
+                None
+            } else if let Some(help_short) = self.get_help_short() {
+                Some(format!("{}{}", self.prefixes.short, help_short))
             } else {
-                // TODO: This isn't super good helpful :/
-                s.push_str(" [opts...]");
-            }
+                Some(format!("{}help", self.prefixes.long))
+            };
+            tokens.push(UsageToken::Options { help, others: self.options.len() > 1 });
         }
         
-        for &(ref name, _) in self.positional.iter() {
-            s.push(' ');
-            s.push_str(name.as_ref());
+        for (name, _) in self.positional.iter() {
+            if self.defaults.contains_key(name) {
+                tokens.push(UsageToken::OptionalPositional(name.clone()));
+            } else {
+                tokens.push(UsageToken::Positional(name.clone()));
+            }
         }
         
-        if let Some((ref name, optional, _)) = self.trail {
-            s.push(' ');
-            if optional {
-                s.push_str(&format!("[{}...]", name));
-            } else {
-                s.push_str(&format!("{} [{}...]", name, name));
+        if let Some(trail) = self.trail_token() {
+            // The separator is only useful to tell trail values from options.
+            if ! self.options.is_empty() {
+                tokens.push(UsageToken::Separator);
             }
+            tokens.push(trail);
         }
         
-        /*if self.subcommands.len() == 1 {
-            s.push(' ');
-            let ref name = self.subcommands[0].0;
-            s.push_str(name.as_ref());
-            s.push_str(" [args...]");
-        } else */  
         if ! self.subcommands.is_empty() {
-            s.push_str(" { ");
-            let last = self.subcommands.len() - 1;
-            for (i, &(ref name, _)) in self.subcommands.iter().enumerate() {
-                s.push_str(name.as_ref());
-                if i != last {
-                    s.push_str(" | ");
-                }
-            }
-            s.push_str(" } ...");
+            let names = self.subcommands.iter().map(|(name, _)| name.clone()).collect();
+            tokens.push(UsageToken::Subcommands(names));
         }
+        tokens
+    }
+    
+    fn write_usage_into(&self, s: &mut String) {
+        let tokens = self.usage_tokens().iter().map(|token| match *token {
+            UsageToken::Separator => format!("[{}]", self.prefixes.long),
+            ref token => token.to_string(),
+        }).collect::<Vec<_>>();
+        s.push_str(&tokens.join(" "));
     }
     
     /// Generates a usage message for this program.
+    ///
+    /// The `[--]` separator is only shown when there are both options and a
+    /// trail, as it is only needed to tell them apart.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, Help};
+    /// let (mut rest, mut verbose) = (Vec::<String>::new(), false);
+    /// assert_eq!("p [t...]", Help::new("p".into(), &[
+    ///     ArgDef::trail("t", true, &mut rest),
+    /// ]).usage_message());
+    /// assert_eq!("p [opts...]", Help::new("p".into(), &[
+    ///     ArgDef::flag("verbose", &mut verbose),
+    /// ]).usage_message());
+    /// assert_eq!("p [opts...] [--] [t...]", Help::new("p".into(), &[
+    ///     ArgDef::trail("t", true, &mut rest),
+    ///     ArgDef::flag("verbose", &mut verbose),
+    /// ]).usage_message());
+    /// ```
     pub fn usage_message(&self) -> String {
         let mut s = String::new();
         self.write_usage_into(&mut s);
@@ -175,43 +563,194 @@ impl<'def> Help<'def> {
         println!("Usage: {}", self.usage_message());
     }
     
+    /// Returns the line that points to the help after an error, like
+    /// `For more information, try '-h' or '--help'.`, if `--help` is defined.
+    /// The help option is written as it would be given, with its short 
+    /// identifier if it has one.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, Help};
+    /// let defs = vec![ArgDef::default_help("").short("h")];
+    /// assert_eq!(Some("For more information, try '-h' or '--help'.".to_string()), 
+    ///            Help::new("prog".into(), &defs).hint_message());
+    /// let defs = vec![ArgDef::default_help("")];
+    /// assert_eq!(Some("For more information, try '--help'.".to_string()), 
+    ///            Help::new("prog".into(), &defs).hint_message());
+    /// assert_eq!(None, Help::new("prog".into(), &[]).hint_message());
+    /// ```
+    pub fn hint_message(&self) -> Option<String> {
+        if ! self.help_defined {
+            return None;
+        }
+        let long = format!("'{}help'", self.prefixes.long);
+        Some(match self.get_help_short() {
+            Some(short) => format!("For more information, try '{}{}' or {}.", self.prefixes.short, short, long),
+            None => format!("For more information, try {}.", long),
+        })
+    }
+    
+    /// Writes a section describing the given options.
+    fn write_options_into(&self, s: &mut String, title: &str, 
+            options: &[&HelpOption<'def>]) {
+        let has_multi_arg_opt = options.iter().any(|&(_, _, kind, _)| {
+            matches!(*kind, HelpOptKind::Count | HelpOptKind::Collect(_))
+        });
+        
+        let has_interrupt = options.iter().any(|&(_, _, kind, _)| {
+            matches!(*kind, HelpOptKind::Interrupt)
+        });
+        
+        let has_required = options.iter().any(|option| self.required.contains(&option.0));
+        
+        let has_legend = has_multi_arg_opt || has_interrupt || has_required;
+        
+        s.push_str(title);
+        s.push_str(":\n");
+        
+        // 'Legend'
+        if has_multi_arg_opt {
+            s.push_str("  ( * ) This option can be given multiple times.\n");
+        }
+        
+        if has_interrupt {
+            s.push_str("  ( X ) This option interrupts normal parsing.\n");
+        }
+        
+        if has_required {
+            s.push_str("  (required) This option must be given.\n");
+        }
+        
+        if has_legend {
+            s.push('\n');
+        }
+        
+        // Required options are listed first, otherwise in definition order.
+        let required = options.iter().filter(|option| self.required.contains(&option.0));
+        let optional = options.iter().filter(|option| ! self.required.contains(&option.0));
+        for &(name, short, kind, help) in required.chain(optional) {
+            s.push_str("  ");
+            s.push_str(&self.prefixes.long);
+            s.push_str(name.as_ref());
+            if let Some(short) = short {
+                s.push_str(", ");
+                s.push_str(&self.prefixes.short);
+                s.push_str(short.as_ref());
+            }
+        
+            // Argument
+            match *kind {
+                HelpOptKind::Setting(ref param)
+                | HelpOptKind::Collect(ref param) => {
+                    s.push(' ');
+                    if let Some(param) = param {
+                        s.push_str(param.as_ref());
+                    } else {
+                        s.push_str(&name.as_ref().to_uppercase());
+                    }
+                    s.push_str(&self.type_hint(name));
+                }
+                _ => {}
+            }
+        
+            // Markers
+            match *kind {
+                HelpOptKind::Collect(_) | HelpOptKind::Count => {
+                    s.push_str(" ( * )");
+                }
+                HelpOptKind::Interrupt => {
+                    s.push_str(" ( X )");
+                }
+                _ => {}
+            }
+            if self.required.contains(name) {
+                s.push_str(" (required)");
+            }
+            if let Some(default) = self.defaults.get(name) {
+                s.push_str(&format!(" [default: {}]", default));
+            }
+        
+            s.push('\n');
+            if let Some(help) = self.full_help(name, help) {
+                write_trimmed_n(s, "      ", help);
+                s.push('\n');
+            }
+        }
+    }
+    
     /// Generates a help message for this program, using the given program
     /// description. The description may be left blank.
+    ///
+    /// Arguments are listed in the order they were defined, except that 
+    /// required options come before the other options in their section, 
+    /// marked with `(required)`.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, Help};
+    /// let (mut a, mut b, mut c, mut d) = (false, false, false, false);
+    /// let (mut e, mut f, mut g, mut h) = (false, false, false, false);
+    /// let defs = vec![
+    ///     ArgDef::flag("zebra", &mut a), ArgDef::flag("apple", &mut b),
+    ///     ArgDef::flag("mango", &mut c), ArgDef::flag("kiwi", &mut d),
+    ///     ArgDef::flag("banana", &mut e), ArgDef::flag("yam", &mut f),
+    ///     ArgDef::flag("cherry", &mut g), ArgDef::flag("lime", &mut h),
+    /// ];
+    /// let message = Help::new("prog".into(), &defs).help_message("");
+    /// let listed: Vec<_> = message.lines()
+    ///     .filter_map(|line| line.trim().split_whitespace().next())
+    ///     .filter(|word| word.starts_with("--"))
+    ///     .collect();
+    /// assert_eq!(listed, vec!["--zebra", "--apple", "--mango", "--kiwi", 
+    ///                         "--banana", "--yam", "--cherry", "--lime"]);
+    /// ```
+    ///
+    /// Hidden options are left out (see `advanced_help_message`). Arguments
+    /// are described by their long help, when they have one 
+    /// (see `ArgDef::help_long`).
     pub fn help_message(&self, description: &str) -> String {
+        self.render_help(description, false)
+    }
+    
+    /// Like `help_message`, but also lists the hidden options, in a separate 
+    /// "Advanced options" section.
+    pub fn advanced_help_message(&self, description: &str) -> String {
+        self.render_help(description, true)
+    }
+    
+    fn render_help(&self, description: &str, show_hidden: bool) -> String {
         let mut s = String::from("Usage:\n  ");
         self.write_usage_into(&mut s);
+        s.push('\n');
         
-        let has_description = description != "";
+        let has_description = trim_and_strip_lines(description).next().is_some();
         let has_positional = (! self.positional.is_empty()) || self.trail.is_some();
-        let has_optional = ! self.options.is_empty();
         let has_subcommands = ! self.subcommands.is_empty();
-        if has_positional || has_optional || has_description || has_subcommands {
-            s.push_str("\n\n");
-        }
         
         if has_description {
+            start_section(&mut s);
             s.push_str("Description:\n");
             write_trimmed_n(&mut s, "  ", description);
         }
         
         if has_positional {
-            s.push('\n');
+            start_section(&mut s);
             s.push_str("Positional arguments:\n");
-            for &(ref name, ref help) in self.positional.iter() {
-                s.push_str(&format!("  {}\n", name));
-                if let &Some(ref help) = help {
+            for (name, help) in self.positional.iter() {
+                match self.defaults.get(name) {
+                    Some(default) => s.push_str(&format!("  {}{} [default: {}]\n", name, self.type_hint(name), default)),
+                    None => s.push_str(&format!("  {}{}\n", name, self.type_hint(name))),
+                }
+                if let Some(help) = self.full_help(name, help) {
                     write_trimmed_n(&mut s, "    ", help);
                 }
                 s.push('\n');
             }
-            if let Some((ref name, optional, ref help)) = self.trail {
-                s.push_str("  ");
-                if optional {
-                    s.push_str(&format!("[{}...]\n", name));
-                } else {
-                    s.push_str(&format!("{} [{}...]\n", name, name));
-                }
-                if let &Some(ref help) = help {
+            if let Some((ref name, _, ref help)) = self.trail {
+                // INVARIANT: There is a synopsis when there is a trail
+                let synopsis = self.trail_synopsis().unwrap();
+                s.push_str(&format!("  {}{}\n", synopsis, self.type_hint(name)));
+                if let Some(help) = self.full_help(name, help) {
                     write_trimmed_n(&mut s, "    ", help);
                 }
                 s.push('\n');
@@ -219,104 +758,321 @@ impl<'def> Help<'def> {
         }
         
         if has_subcommands {
-            s.push('\n');
+            start_section(&mut s);
             s.push_str("Subcommands:\n");
-            for &(ref name, ref help) in self.subcommands.iter() {
+            for (name, help) in self.subcommands.iter() {
                 s.push_str(&format!("  {}\n", name));
-                if let &Some(ref help) = help {
+                if let Some(help) = self.full_help(name, help) {
                     write_trimmed_n(&mut s, "    ", help);
                 }
                 s.push('\n');
             }
         }
         
-        if has_optional {
-            if ! (has_positional || has_subcommands) {
-                s.push('\n');
-            }
-            
-            let has_multi_arg_opt = self.options.iter().any(|&(_, _, ref kind, _)| {
-                match *kind {
-                    HelpOptKind::Count | HelpOptKind::Collect(_) => true,
-                    _ => false
-                }
-            });
-            
-            let has_interrupt = self.options.iter().any(|&(_, _, ref kind, _)| {
-                match *kind {
-                    HelpOptKind::Interrupt => true,
-                    _ => false
-                }
-            });
-            
-            let has_legend = has_multi_arg_opt || has_interrupt;
-            
-            s.push_str("Optional arguments:\n");
-            
-            // 'Legend'
-            if has_multi_arg_opt {
-                s.push_str("  ( * ) This option can be given multiple times.\n");
+        let visible = self.options.iter().filter(|option| ! self.hidden.contains(&option.0)).collect::<Vec<_>>();
+        if ! visible.is_empty() {
+            start_section(&mut s);
+            self.write_options_into(&mut s, "Optional arguments", &visible);
+        }
+        
+        let advanced = self.options.iter().filter(|option| self.hidden.contains(&option.0)).collect::<Vec<_>>();
+        if show_hidden && ! advanced.is_empty() {
+            start_section(&mut s);
+            self.write_options_into(&mut s, "Advanced options", &advanced);
+        }
+        
+        self.write_environment_into(&mut s, show_hidden);
+        
+        // Entries are separated by empty lines, so drop the last one.
+        while s.ends_with("\n\n") {
+            s.pop();
+        }
+        s
+    }
+    
+    /// Prints a help message for this program, using the given program
+    /// description. The description may be left blank.
+    pub fn print_help(&self, description: &str) {
+        print!("{}", self.help_message(description));
+    }
+    
+    /// Prints an advanced help message for this program (see 
+    /// `advanced_help_message`).
+    pub fn print_advanced_help(&self, description: &str) {
+        print!("{}", self.advanced_help_message(description));
+    }
+    
+    /// Writes the help message (see `help_message`) to the given writer.
+    ///
+    /// Sections without content are left out, and the sections are separated
+    /// by exactly one empty line.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, Help};
+    /// fn golden(defs: &[ArgDef], description: &str) -> String {
+    ///     let mut out = Vec::new();
+    ///     Help::new("prog".into(), defs).write_help(description, &mut out).unwrap();
+    ///     String::from_utf8(out).unwrap()
+    /// }
+    /// let (mut verbose, mut file) = (false, String::new());
+    ///
+    /// assert_eq!("Usage:\n  prog\n", golden(&[], ""));
+    /// assert_eq!("Usage:\n  prog\n\nDescription:\n  A program.\n", 
+    ///            golden(&[], "A program."));
+    /// assert_eq!("Usage:\n  prog [opts...]\n\n\
+    ///             Optional arguments:\n  --verbose, -v\n      Talks more.\n", 
+    ///            golden(&[ArgDef::flag("verbose", &mut verbose).short("v").help("Talks more.")], ""));
+    /// assert_eq!("Usage:\n  prog file\n\n\
+    ///             Positional arguments:\n  file\n    The file.\n", 
+    ///            golden(&[ArgDef::positional("file", &mut file).help("The file.")], ""));
+    /// assert_eq!("Usage:\n  prog { add } ...\n\n\
+    ///             Subcommands:\n  add\n    Adds.\n", 
+    ///            golden(&[ArgDef::subcommand("add", |_, _| Ok(None)).help("Adds.")], ""));
+    /// assert_eq!("Usage:\n  prog [opts...] file\n\n\
+    ///             Description:\n  A program.\n\n\
+    ///             Positional arguments:\n  file\n    The file.\n\n\
+    ///             Optional arguments:\n  --verbose, -v\n      Talks more.\n", 
+    ///            golden(&[
+    ///                ArgDef::positional("file", &mut file).help("The file."),
+    ///                ArgDef::flag("verbose", &mut verbose).short("v").help("Talks more."),
+    ///            ], "A program."));
+    /// ```
+    pub fn write_help<W: Write>(&self, description: &str, out: &mut W) -> io::Result<()> {
+        out.write_all(self.help_message(description).as_bytes())
+    }
+    
+    /// Generates a compact help message, with only the usage and the names of
+    /// the options.
+    ///
+    /// ```text
+    /// Usage: prog [-h, OPTS...] FILE
+    /// Options: -h/--help, -v/--verbose, --output
+    /// See '--help' for details.
+    /// ```
+    pub fn compact_help(&self) -> String {
+        let mut s = format!("Usage: {}\n", self.usage_message());
+        let names = self.options.iter()
+            .filter(|option| ! self.hidden.contains(&option.0))
+            .map(|(name, short, _, _)| match *short {
+                Some(ref short) => format!("{}{}/{}{}", self.prefixes.short, short, self.prefixes.long, name),
+                None => format!("{}{}", self.prefixes.long, name),
+            })
+            .collect::<Vec<_>>();
+        if ! names.is_empty() {
+            s.push_str(&format!("Options: {}\n", names.join(", ")));
+        }
+        if self.help_defined {
+            s.push_str(&format!("See '{}help' for details.\n", self.prefixes.long));
+        }
+        s
+    }
+    
+    /// Describes the version of this program as a JSON object, like 
+    /// `{"version":"1.2.3","name":"prog"}`, for tools that read it (see
+    /// `ArgDef::default_version_json`).
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::Help;
+    /// let help = Help::new("prog".into(), &[]);
+    /// assert_eq!(r#"{"version":"1.2.3","name":"prog"}"#, help.version_json("1.2.3"));
+    /// ```
+    pub fn version_json(&self, version: &str) -> String {
+        json_object(&[("version", Some(version)), ("name", Some(&self.program))])
+    }
+    
+    /// Writes the version of this program as a line of JSON (see 
+    /// `version_json`) to the given writer.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::Help;
+    /// let help = Help::new("prog".into(), &[]);
+    /// let mut out = Vec::new();
+    /// help.write_version_json("1.2.3", &mut out).unwrap();
+    /// assert_eq!(b"{\"version\":\"1.2.3\",\"name\":\"prog\"}\n", &out[..]);
+    /// ```
+    pub fn write_version_json<W: Write>(&self, version: &str, out: &mut W) -> io::Result<()> {
+        writeln!(out, "{}", self.version_json(version))
+    }
+    
+    /// Generates a Markdown document describing this program, with the same
+    /// information as the help message.
+    ///
+    /// This is useful for embedding the documentation of a program in a 
+    /// README or on a website.
+    pub fn markdown(&self, description: &str) -> String {
+        let mut s = format!("# {}\n", self.program);
+        
+        if trim_and_strip_lines(description).next().is_some() {
+            s.push('\n');
+            write_trimmed_n(&mut s, "", description);
+        }
+        
+        s.push_str("\n## Usage\n\n```text\n");
+        self.write_usage_into(&mut s);
+        s.push_str("\n```\n");
+        
+        if ! self.positional.is_empty() || self.trail.is_some() {
+            s.push_str("\n## Positional arguments\n\n");
+            for (name, help) in &self.positional {
+                write_markdown_item(&mut s, &format!("`{}`{}", name, self.type_hint(name)), help);
             }
-            
-            if has_interrupt {
-                s.push_str("  ( X ) This option interrupts normal parsing.\n");
+            if let Some((ref name, _, ref help)) = self.trail {
+                // INVARIANT: There is a synopsis when there is a trail
+                let synopsis = self.trail_synopsis().unwrap();
+                let item = format!("`{}`{}", synopsis, self.type_hint(name));
+                write_markdown_item(&mut s, &item, help);
             }
-            
-            if has_legend {
-                s.push('\n');
+        }
+        
+        if ! self.subcommands.is_empty() {
+            s.push_str("\n## Subcommands\n\n");
+            for (name, help) in &self.subcommands {
+                write_markdown_item(&mut s, &format!("`{}`", name), help);
             }
-            
-            
-            for &(ref name, ref short, ref kind, ref help) in self.options.iter() {
-                s.push_str("  ");
-                s.push_str("--");
-                s.push_str(name.as_ref());
-                if let &Some(ref short) = short {
-                    s.push_str(", ");
-                    s.push('-');
-                    s.push_str(short.as_ref());
+        }
+        
+        if self.options.iter().any(|option| ! self.hidden.contains(&option.0)) {
+            s.push_str("\n## Options\n\n");
+            for (name, short, kind, help) in &self.options {
+                if self.hidden.contains(name) {
+                    continue;
+                }
+                let mut item = format!("`{}{}`", self.prefixes.long, name);
+                if let Some(ref short) = *short {
+                    item.push_str(&format!(", `{}{}`", self.prefixes.short, short));
                 }
-                
-                // Argument
                 match *kind {
-                    HelpOptKind::Setting(ref param)
-                    | HelpOptKind::Collect(ref param) => {
-                        s.push(' ');
-                        if let &Some(ref param) = param {
-                            s.push_str(param.as_ref());
-                        } else {
-                            s.push_str(&name.as_ref().to_uppercase());
+                    HelpOptKind::Setting(ref param) | HelpOptKind::Collect(ref param) => {
+                        match *param {
+                            Some(ref param) => item.push_str(&format!(" `{}`", param)),
+                            None => item.push_str(&format!(" `{}`", name.to_uppercase())),
                         }
+                        item.push_str(&self.type_hint(name));
                     }
                     _ => {}
                 }
-                
-                // Markers
                 match *kind {
                     HelpOptKind::Collect(_) | HelpOptKind::Count => {
-                        s.push_str(" ( * )");
+                        item.push_str(" *(can be given multiple times)*");
                     }
                     HelpOptKind::Interrupt => {
-                        s.push_str(" ( X )");
+                        item.push_str(" *(interrupts normal parsing)*");
                     }
                     _ => {}
                 }
-                
-                s.push('\n');
-                if let &Some(ref help) = help {
-                    write_trimmed_n(&mut s, "      ", help);
-                    s.push('\n');
-                }
+                write_markdown_item(&mut s, &item, help);
             }
         }
         
         s
     }
     
-    /// Prints a help message for this program, using the given program
-    /// description. The description may be left blank.
-    pub fn print_help(&self, description: &str) {
-        print!("{}", self.help_message(description));
+    /// Like `print_help`, but shows the message through the user's pager 
+    /// (`$PAGER`, or `less`), when stdout is a terminal that is too short 
+    /// for the message.
+    ///
+    /// The height of the terminal is taken from `$LINES` if it is exported,
+    /// and is otherwise asked with `stty size`. When neither works (like on 
+    /// platforms without `stty`), the terminal is assumed to be 24 lines high.
+    ///
+    /// The message is printed directly when no pager can be started.
+    pub fn page_help(&self, description: &str) {
+        let message = self.help_message(description);
+        if ! page(&message) {
+            print!("{}", message);
+        }
+    }
+    
+    /// Finds the shortest prefix of each option name that no other option
+    /// name starts with.
+    ///
+    /// An option whose name is a prefix of another option's name (`in` and
+    /// `include`) can only be given by its full name.
+    pub fn min_prefixes(&self) -> HashMap<String, String> {
+        let mut prefixes = HashMap::new();
+        for (name, _, _, _) in &self.options {
+            let others = self.options.iter()
+                .map(|(other, _, _, _)| other)
+                .filter(|other| *other != name)
+                .collect::<Vec<_>>();
+            let ends = name.char_indices().skip(1).map(|(i, _)| i)
+                .chain(Some(name.len()));
+            let mut prefix = name.as_ref();
+            for end in ends {
+                let candidate = &name[..end];
+                if ! others.iter().any(|other| other.starts_with(candidate)) {
+                    prefix = candidate;
+                    break;
+                }
+            }
+            prefixes.insert(name.to_string(), prefix.to_string());
+        }
+        prefixes
+    }
+}
+
+/// A part of a usage message (see `Help::usage_tokens`).
+///
+/// Each token is displayed as it is written in `Help::usage_message`, where
+/// the tokens are separated by spaces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UsageToken<'def> {
+    /// The 'command path' of the program, like `cargo new`.
+    Program(String),
+    /// The options, with how to ask for help (`-h` or `--help`) if it is 
+    /// defined, and whether there are other options. 
+    /// Displayed like `[-h, OPTS...]`, or `[opts...]` without help.
+    Options { help: Option<String>, others: bool },
+    /// A positional argument, by name.
+    Positional(Cow<'def, str>),
+    /// A positional argument with a default, by name. Displayed like `[out]`.
+    OptionalPositional(Cow<'def, str>),
+    /// The `[--]` that can end the options before the trail.
+    Separator,
+    /// The trail, with the (min, max) number of values if it has bounds.
+    /// Displayed like `[file...]`, `file file [file...]` or `file (2-5)`.
+    Trail { name: Cow<'def, str>, optional: bool, bounds: Option<(usize, Option<usize>)> },
+    /// The names of the subcommands. Displayed like `{ add | remove } ...`.
+    Subcommands(Vec<Cow<'def, str>>),
+}
+
+impl<'def> fmt::Display for UsageToken<'def> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UsageToken::Program(ref program) => write!(f, "{}", program),
+            UsageToken::Options { ref help, others } => {
+                match *help {
+                    Some(ref help) if others => write!(f, "[{}, OPTS...]", help),
+                    Some(ref help) => write!(f, "[{}]", help),
+                    // TODO: This isn't super good helpful :/
+                    None => write!(f, "[opts...]"),
+                }
+            }
+            UsageToken::Positional(ref name) => write!(f, "{}", name),
+            UsageToken::OptionalPositional(ref name) => write!(f, "[{}]", name),
+            UsageToken::Separator => write!(f, "[--]"),
+            UsageToken::Trail { ref name, optional, bounds } => {
+                match bounds {
+                    Some((min, Some(max))) if min == max => write!(f, "{} ({})", name, max),
+                    Some((min, Some(max))) => write!(f, "{} ({}-{})", name, min, max),
+                    Some((min, None)) => {
+                        for _ in 0..min {
+                            write!(f, "{} ", name)?;
+                        }
+                        write!(f, "[{}...]", name)
+                    }
+                    None if optional => write!(f, "[{}...]", name),
+                    None => write!(f, "{} [{}...]", name, name),
+                }
+            }
+            UsageToken::Subcommands(ref names) => {
+                let names = names.iter().map(|name| name.as_ref()).collect::<Vec<_>>();
+                write!(f, "{{ {} }} ...", names.join(" | "))
+            }
+        }
     }
 }
 
@@ -0,0 +1,144 @@
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::File;
+use std::io::{self, Read};
+use argdef::{ArgDef, ArgDefKind};
+use config::ParseConfig;
+use parse::{parse_with, truthiness, ParseError, ParseFailure, FailureKind};
+use summary::ParseSummary;
+
+/// A source of argument values for `parse_layered`.
+#[derive(Debug, Clone, Copy)]
+pub enum Source<'a, T: 'a> {
+    /// The command-line arguments.
+    Cli(&'a [T]),
+    /// Environment variables with the given prefix. The variable of an
+    /// option is the prefix followed by its name in upper case, with dashes
+    /// as underscores, so `Env("APP_")` reads `--log-level` from
+    /// `APP_LOG_LEVEL`. Flags read their variables like `ArgDef::env` does,
+    /// so `1`, `true`, `yes` and `on` set them, and `0`, `false`, `no`, 
+    /// `off` and the empty string clear them, regardless of case.
+    Env(&'a str),
+    /// A file of `name = value` lines, with the (long) names of options.
+    /// Empty lines and lines starting with `#` are skipped. The file is
+    /// skipped if it doesn't exist.
+    File(&'a str),
+}
+
+/// Parses the arguments from several sources, where the sources that come
+/// first win over those that come after them.
+///
+/// Only the command-line arguments are parsed as such; the other sources
+/// are only read for options, which then get their values as defaults (see
+/// `ParseConfig::defaults`). So for each option, the first source that has
+/// a value for it gives that value, and the later ones are ignored:
+///
+/// - Settings take the value.
+/// - Flags take `true` or `false`, and counts take a number.
+/// - Collectors take the value as one item (or several, when they have a
+///   delimiter). Values from different sources are not combined, and any
+///   value on the command line replaces them all.
+/// - Positional arguments, trails, subcommands and interrupts are only read
+///   from the command line.
+///
+/// The command line usually comes first, but any order works. Naming an
+/// unknown option in a file, or a file that can't be read, fails the parse.
+/// Otherwise this behaves like `parse_with`, with the default configuration.
+///
+/// # Example
+/// ```
+/// # use argonaut::{ArgDef, parse_layered, Source};
+/// # use std::env;
+/// env::set_var("DOCS_LAYERED_HOST", "example.com");
+/// env::set_var("DOCS_LAYERED_PORT", "8080");
+/// env::set_var("DOCS_LAYERED_VERBOSE", "1");
+/// let (mut host, mut port, mut verbose) = (None::<String>, None::<u16>, false);
+/// parse_layered("prog", &[Source::Cli(&["--port", "9000"]), Source::Env("DOCS_LAYERED_")], vec![
+///     ArgDef::setting("host", &mut host),
+///     ArgDef::setting("port", &mut port),
+///     ArgDef::flag("verbose", &mut verbose),
+/// ]).unwrap();
+/// assert_eq!(Some("example.com".to_string()), host);
+/// assert_eq!(Some(9000), port);
+/// assert!(verbose);
+/// ```
+pub fn parse_layered<'def, 'tar, 'a, T, P>(program: P, sources: &[Source<'a, T>],
+    definitions: Vec<ArgDef<'def, 'tar>>)
+    -> Result<ParseSummary<'def>, ParseError<'def>>
+  where T: Borrow<str>,
+        P: Into<String>
+{
+    let program = program.into();
+    let names = definitions.iter().filter(|def| def.takes_default())
+        .map(|def| def.name.to_string()).collect::<Vec<_>>();
+    let flags = definitions.iter().filter(|def| matches!(def.kind, ArgDefKind::Flag { .. }))
+        .map(|def| def.name.to_string()).collect::<HashSet<_>>();
+    let mut args: &[T] = &[];
+    let mut config = ParseConfig::new();
+    // The later sources are added first, so that the earlier ones replace them.
+    for source in sources.iter().rev() {
+        let defaults = match *source {
+            Source::Cli(cli) => {
+                args = cli;
+                continue;
+            }
+            Source::Env(prefix) => env_values(prefix, &names, &flags),
+            Source::File(path) => match file_values(path) {
+                Ok(defaults) => defaults,
+                Err(msg) => {
+                    let help = config.help(program, &definitions);
+                    let failure = ParseFailure::new(FailureKind::BadValue, msg).value(path);
+                    config.reporter.report(&failure, &help);
+                    return Err(ParseError::SubParseFailed);
+                }
+            },
+        };
+        config = config.defaults(defaults);
+    }
+    parse_with(program, args, definitions, config)
+}
+
+/// Returns the environment variable that gives the option its value.
+pub(crate) fn env_var(prefix: &str, name: &str) -> String {
+    format!("{}{}", prefix, name.to_uppercase().replace('-', "_"))
+}
+
+/// Reads the values of the given options from the environment. The values
+/// of flags are read as booleans, and left as they are if they aren't ones.
+fn env_values(prefix: &str, names: &[String], flags: &HashSet<String>) -> HashMap<String, String> {
+    names.iter().filter_map(|name| {
+        env::var(env_var(prefix, name)).ok().map(|value| {
+            let value = match truthiness(&value) {
+                Some(enabled) if flags.contains(name) => enabled.to_string(),
+                _ => value,
+            };
+            (name.clone(), value)
+        })
+    }).collect()
+}
+
+/// Reads the values of options from a file of `name = value` lines.
+fn file_values(path: &str) -> Result<HashMap<String, String>, String> {
+    let mut contents = String::new();
+    match File::open(path).and_then(|mut file| file.read_to_string(&mut contents)) {
+        Ok(_) => {}
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(e) => return Err(format!("Could not read '{}': {}", path, e)),
+    }
+    let mut values = HashMap::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.find('=') {
+            Some(index) => {
+                values.insert(line[..index].trim().to_string(), line[index + 1..].trim().to_string());
+            }
+            None => return Err(format!("Expected 'name = value' on line {} of '{}', not '{}'",
+                number + 1, path, line)),
+        }
+    }
+    Ok(values)
+}
@@ -12,23 +12,40 @@ The lifetime of argument definition passed to `parse`
 The lifetime of target pointers used when defining arguments.
 */
 
-#![feature(conservative_impl_trait)]
-#![feature(unicode)]
-
-extern crate std_unicode;
+// The crate is written in the 2015 style, with bare trait objects.
+#![allow(bare_trait_objects)]
 
 mod argdef;
+mod config;
 mod help;
+mod layered;
 mod parse;
-
-pub use argdef::{ArgDef, SingleTarget, CollectionTarget, OptionTarget};
-pub use parse::{parse, parse_plain, ParseError};
+mod program;
+mod report;
+mod split;
+mod summary;
+
+pub use argdef::{ArgDef, OptHandle, SingleTarget, CollectionTarget, OptionTarget, Joined, Indexed, Choice, OrderedMap,
+                 InterruptFlow, DefaultContext};
+#[doc(hidden)]
+pub use argdef::parse_value;
+pub use config::ParseConfig;
+pub use help::{Help, HelpOptKind, UsageToken};
+pub use layered::{parse_layered, Source};
+pub use parse::{parse, parse_plain, parse_with, parse_plain_with, parse_two_pass, parse_str, parse_partial, parse_or_exit,
+                parse_os, parse_os_with,
+                dry_run_parse, DryRunOutcome, ParseError, ParseFailure, FailureKind};
+pub use program::Program;
+pub use report::{ErrorReporter, PrintReporter, JsonReporter};
+pub use split::split_line;
+pub use summary::{ParseSummary, OptKey, FlagState};
 
 /*
 DESIGN: Do I wait with assigning values until all arguments have been 'satisfied'?
 Or do I just start parsing/assigning as soon as possible so that bad arguments
 are caught faster?
-For now it'll be 2, since that seems simpler
+For now it'll be 2, since that seems simpler. Option 1 can be chosen with
+ParseConfig::deferred_parsing.
 
 # option 1
 read through the arguments and assign each to a matching option
@@ -43,7 +60,7 @@ return success
 Tasks
 
 Optional
-- Implement a validate->assign->modify procedure in parse
+- Implement a modify step after validate->assign in parse
 - Make a passthrough argument (cargo run -- --help)
 - Add examples to README and library top-level documentation
 - Add tests
@@ -57,6 +74,7 @@ Done
 - Implement multi-target for all std::collections
 - Let parse results pass either Option<i32> or Option<T> to facilitate 'outer' abort.
 - Make a 'collect' argument (gcc -i foo.h -i bar.h)
+- Implement a validate->assign procedure in parse (ParseConfig::deferred_parsing)
 
 Abandoned
 - Make a default handler function for parse results.
@@ -1,28 +1,137 @@
-use argdef::{SingleTarget, CollectionTarget, OptionTarget, ArgDef, ArgDefKind, SubCmd};
+use argdef::{SingleTarget, CollectionTarget, OptionTarget, ArgDef, ArgDefKind, SubCmd, InterruptCallback, InterruptFlow,
+             CountCallback, DefaultCallback, DefaultContext, Sign, parse_value};
 use help::Help;
+use config::{ParseConfig, Prefixes};
+use summary::ParseSummary;
+use split::split_line;
+use report::json_object;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::borrow::{Cow, Borrow};
 use std::rc::Rc;
+use std::iter::Peekable;
+use std::ffi::OsStr;
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+
+/// Loads the defaults selected by the config option (see `parse_two_pass`).
+type ConfigLoader<'a> = FnMut(&str) -> Result<HashMap<String, String>, String> + 'a;
 
 /// References to the targets of non-positional arguments.
 //#[derive(Debug)]
 pub enum TargetRef<'def, 'tar> {
     Flag(&'tar mut bool),
     Count(&'tar mut usize),
-    Setting(&'tar mut OptionTarget),
-    Interrupt(Box<FnMut(Rc<Help<'def>>)>),
-    Collect(&'tar mut CollectionTarget),
+    Features(&'tar mut HashMap<String, bool>),
+    Setting {
+        target: &'tar mut OptionTarget,
+        greedy: bool,
+        overridable: bool,
+        presence: Option<&'tar mut bool>,
+        file_indirect: bool,
+    },
+    Interrupt {
+        callback: InterruptCallback<'def>,
+        exit_code: i32,
+    },
+    Collect {
+        target: &'tar mut CollectionTarget,
+        variadic: bool,
+        delimiter: Option<char>,
+        counter: Option<&'tar mut usize>,
+        key_separator: Option<char>,
+        terminator: Option<(Cow<'def, str>, bool)>,
+    },
+}
+
+/// A value that is parsed into its target at the end of a deferred parse.
+enum Deferred<'def, 'tar> {
+    Positional(Cow<'def, str>, &'tar mut SingleTarget, String),
+    Trail(String),
+    // (name, option as given, value)
+    Option(Cow<'def, str>, String, String),
 }
 
+/// The (name, satisfied, target, delimiter) of the trail.
+type Trail<'def, 'tar> = (Cow<'def, str>, bool, &'tar mut CollectionTarget, Option<char>);
+
 /// Sorted argument definitions. Updated mutably during the parse.
 //#[derive(Debug)]
 pub struct ParseState<'def, 'tar> {
     positional: VecDeque<(Cow<'def, str>, &'tar mut SingleTarget)>,
-    // (satisfied, target)
-    trail: Option<(Cow<'def, str>, bool, &'tar mut CollectionTarget)>,
+    trail: Option<Trail<'def, 'tar>>,
+    // The command positional, and the target for the arguments after it
+    command: Option<(Cow<'def, str>, &'tar mut Vec<String>)>,
+    // (min, max) values of the trail, if it has bounds
+    trail_bounds: Option<(usize, Option<usize>)>,
+    // The number of values given to the trail
+    trail_count: usize,
     subcommands: HashMap<Cow<'def, str>, SubCmd<'def>>,
     options: HashMap<Cow<'def, str>, TargetRef<'def, 'tar>>,
     short_map: HashMap<Cow<'def, str>, Cow<'def, str>>,
+    // The `no-` names of features options, and the options they disable for
+    negations: HashMap<String, Cow<'def, str>>,
+    prefixes: Prefixes,
+    // (option, required option), in definition order
+    requires: Vec<(Cow<'def, str>, Cow<'def, str>)>,
+    // (option, conflicting option), in definition order
+    conflicts: Vec<(Cow<'def, str>, Cow<'def, str>)>,
+    // (option, implied flag), in definition order
+    implies: Vec<(Cow<'def, str>, Cow<'def, str>)>,
+    // (option, option it must be used together with), in definition order
+    together: Vec<(Cow<'def, str>, Cow<'def, str>)>,
+    // (option, short) of the options that must be given
+    required: Vec<(Cow<'def, str>, Option<Cow<'def, str>>)>,
+    // The values to parse at the end, if parsing is deferred
+    deferred: Option<Vec<Deferred<'def, 'tar>>>,
+    // The allowed values of the arguments that have choices, and whether
+    // they are matched regardless of case
+    choices: HashMap<Cow<'def, str>, (Vec<Cow<'def, str>>, bool)>,
+    // The names of the arguments that must not be given empty values
+    non_empty: HashSet<Cow<'def, str>>,
+    // The names of the arguments whose values are trimmed
+    trimmed: HashSet<Cow<'def, str>>,
+    // The names of the collectors that take lists, and skip empty values
+    lists: HashSet<Cow<'def, str>>,
+    // The signs that the numeric values of arguments must have
+    signs: HashMap<Cow<'def, str>, Sign>,
+    // The values given so far to the collectors that require unique values
+    unique: HashMap<Cow<'def, str>, HashSet<String>>,
+    // The options that cannot be given before a subcommand
+    without_subcommand: Vec<Cow<'def, str>>,
+    // The options that can only be given with some subcommands, and those
+    // subcommands, in definition order
+    scopes: Vec<(Cow<'def, str>, Vec<Cow<'def, str>>)>,
+    // The options that were given or defaulted, in canonical form
+    effective_options: Vec<String>,
+    // The positional and trail values that were given
+    effective_values: Vec<String>,
+    // Whether the targets are left untouched (see `dry_run_parse`)
+    dry_run: bool,
+    // The settings with computed defaults, in definition order
+    computed_defaults: Vec<(Cow<'def, str>, DefaultCallback<'def>)>,
+    // The raw values of the positionals and settings, for computed defaults
+    context: DefaultContext,
+    // The settings whose values are templates, in definition order
+    templated: Vec<Cow<'def, str>>,
+    // The options that are read before the others, and whether they have been
+    eager: HashSet<Cow<'def, str>>,
+    eager_read: bool,
+    // The counts with a callback to run at a threshold
+    thresholds: HashMap<Cow<'def, str>, (usize, CountCallback<'def>)>,
+    // (flag, environment variable) of the flags read from the environment
+    env_flags: Vec<(Cow<'def, str>, Cow<'def, str>)>,
+    // The default values of the optional positionals
+    positional_defaults: HashMap<Cow<'def, str>, Cow<'def, str>>,
+    // The (max distance, max count) of the options suggested for unknown ones
+    suggestions: (usize, usize),
+    // Whether long options can be given by unambiguous prefixes of their names
+    abbreviations: bool,
+    // Whether long options are matched regardless of case
+    ignore_case: bool,
 }
 
 impl<'def, 'tar> ParseState<'def, 'tar> {
@@ -31,73 +140,1076 @@ impl<'def, 'tar> ParseState<'def, 'tar> {
         self.options.keys().find(|k| k.as_ref() == option).unwrap().clone()
     }
     
-    /// Attempts to find a target from the given option.
-    fn get_target<'a>(&'a mut self, option: &str, help: Rc<Help<'def>>)
-            -> Result<(Cow<'def, str>, &'a mut TargetRef<'def, 'tar>), ParseError<'def>> {
-        let mut key = &option[2..];
-        if ! option.starts_with("--") {
-            if let Some(mapped_key) = self.short_map.get(&option[1..]) {
-                key = mapped_key.as_ref();
-            } else {
-                return ParseError::parse(format!("Unknown option: '{}'", option), help);
+    /// Attempts to find the name of the defined option matching the given one.
+    fn get_name(&self, option: &str, help: Rc<Help<'def>>) 
+            -> Result<Cow<'def, str>, ParseError<'def>> {
+        let key = match self.prefixes.split(option) {
+            Some((true, key)) => key,
+            Some((false, short)) if self.short_map.contains_key(short) => {
+                self.short_map[short].as_ref()
             }
-        }
-        if ! self.options.contains_key(key) {
-            return ParseError::parse(format!("Unknown option '{}'", option), help);
+            _ => {
+                return ParseError::parse(ParseFailure::new(FailureKind::UnknownOption, 
+                    format!("Unknown option: '{}'", option)).option(option), help);
+            }
+        };
+        let key = match self.match_long(key) {
+            Ok(Some(key)) => key,
+            Ok(None) => {
+                let suggestions = self.suggest(key);
+                let msg = if suggestions.is_empty() {
+                    format!("Unknown option '{}'", option)
+                } else {
+                    format!("Unknown option '{}' (did you mean {}?)", option, suggestions.join(", "))
+                };
+                return ParseError::parse(ParseFailure::new(FailureKind::UnknownOption, msg).option(option), help);
+            }
+            Err(matches) => {
+                return ParseError::parse(ParseFailure::new(FailureKind::AmbiguousOption, 
+                    format!("Ambiguous option '{}' ({})", option, matches.join(", "))).option(option), help);
+            }
+        };
+        if let Some(name) = self.negations.get(key) {
+            return Ok(name.clone());
         }
         // INVARIANT: key is contained
-        let name = self.get_interned_name(key);
-        let target = self.options.get_mut(key).unwrap();
-        Ok((name, target))
+        Ok(self.get_interned_name(key))
+    }
+    
+    /// Finds the defined long option (or `no-` negation) that the given name
+    /// stands for. With abbreviations, an unambiguous prefix of a name also
+    /// matches it, and a prefix of several names fails with them, sorted.
+    /// When the case is ignored, names that only differ by case match too.
+    fn match_long<'a>(&'a self, key: &'a str) -> Result<Option<&'a str>, Vec<&'a str>> {
+        let names = || self.options.keys().map(|name| name.as_ref())
+            .chain(self.negations.keys().map(|name| name.as_str()));
+        if let Some(name) = names().find(|&name| name == key) {
+            return Ok(Some(name));
+        }
+        let lower = key.to_lowercase();
+        if self.ignore_case {
+            // The definitions can't hold two names that only differ by case.
+            if let Some(name) = names().find(|name| name.to_lowercase() == lower) {
+                return Ok(Some(name));
+            }
+        }
+        if ! self.abbreviations || key.is_empty() {
+            return Ok(None);
+        }
+        let ignore_case = self.ignore_case;
+        let mut matches = names().filter(|name| if ignore_case {
+            name.to_lowercase().starts_with(&lower)
+        } else {
+            name.starts_with(key)
+        }).collect::<Vec<_>>();
+        match matches.len() {
+            0 => Ok(None),
+            1 => Ok(Some(matches[0])),
+            _ => {
+                matches.sort();
+                Err(matches)
+            }
+        }
+    }
+    
+    /// Returns the defined long options that the unknown one may be a typo 
+    /// of, closest first, as they would be given.
+    fn suggest(&self, key: &str) -> Vec<String> {
+        let (max_distance, max_count) = self.suggestions;
+        let mut close = self.options.keys().map(|name| name.as_ref())
+            .chain(self.negations.keys().map(|name| name.as_str()))
+            .map(|name| (edit_distance(key, name), name))
+            .filter(|&(distance, _)| distance <= max_distance)
+            .collect::<Vec<_>>();
+        close.sort();
+        close.into_iter().take(max_count)
+            .map(|(_, name)| format!("'{}{}'", self.prefixes.long, name))
+            .collect()
     }
     
+    /// Returns whether the argument is `--` or an option that is defined.
+    fn is_known_option(&self, arg: &str) -> bool {
+        if arg == self.prefixes.long {
+            return true;
+        }
+        match self.prefixes.split(self.prefixes.split_attached(arg).0) {
+            Some((true, name)) => self.match_long(name) != Ok(None),
+            Some((false, short)) => self.short_map.contains_key(short),
+            None => false,
+        }
+    }
     
-    fn read_option<'arg, I>(&mut self, option: &str, args: &mut I, 
-        given_values: &mut HashSet<Cow<'def, str>>, help: Rc<Help<'def>>) 
-        -> Result<Option<Cow<'def, str>>, ParseError<'def>>
+    /// Fails if the option is given before the positional arguments, when the
+    /// config requires them to come first. Interrupts can always be given.
+    fn check_option_order(&self, arg: &str, help: Rc<Help<'def>>, config: &ParseConfig<'def>) 
+            -> Result<(), ParseError<'def>> {
+        if ! config.positionals_first || self.positional.is_empty() {
+            return Ok(());
+        }
+        let option = self.prefixes.split_attached(arg).0;
+        if let Ok(name) = self.get_name(option, help.clone()) {
+            if let TargetRef::Interrupt { .. } = self.options[&name] {
+                return Ok(());
+            }
+        }
+        ParseError::parse(ParseFailure::new(FailureKind::MisplacedOption, 
+            format!("Options must follow positional arguments, but '{}' was given before '{}'", 
+                arg, self.positional[0].0)).option(option), help)
+    }
+    
+    /// Returns whether the argument can be read at this point of the parse,
+    /// rather than failing it.
+    fn can_read(&self, arg: &str, options_ended: bool, config: &ParseConfig<'def>) -> bool {
+        if options_ended {
+            // Fall through to the positional values
+        } else if arg == self.prefixes.long {
+            return true;
+        } else if config.plus_toggles && arg.starts_with("+") && arg != "+" {
+            return self.options.contains_key(&arg[1..]) || self.short_map.contains_key(&arg[1..]);
+        } else if self.prefixes.split(arg).is_some() && ! self.is_value(arg, config) {
+            return self.is_known_option(arg) || self.passes_to_trail(arg, config);
+        }
+        ! self.positional.is_empty() || self.trail.is_some() || self.subcommands.contains_key(arg)
+    }
+    
+    /// Takes every argument until the next known option (or `--`), and joins
+    /// them with spaces.
+    fn read_greedy<'arg, I>(&self, args: &mut Peekable<I>) -> Option<String> 
       where I: Iterator<Item=&'arg str>
+    {
+        let mut words = Vec::new();
+        while let Some(&arg) = args.peek() {
+            if self.is_known_option(arg) {
+                break;
+            }
+            words.push(arg);
+            args.next();
+        }
+        if words.is_empty() {
+            None
+        } else {
+            Some(words.join(" "))
+        }
+    }
+    
+    /// Returns whether the given dash-prefixed argument should be read as a
+    /// value rather than looked up as an option.
+    ///
+    /// A lone dash (like `-` for stdin) is always a value. An argument 
+    /// starting with a dash and a digit is always a value when the config 
+    /// says so. Otherwise a negative number like `-3` or `-2.5` is an option
+    /// if a short identifier with that name (`3`) is defined, a value when 
+    /// negative numbers are enabled in the config, and an unknown option when
+    /// they are not.
+    fn is_value(&self, arg: &str, config: &ParseConfig<'def>) -> bool {
+        if arg == self.prefixes.short {
+            return true;
+        }
+        if config.leading_dash_digit_values && starts_with_prefix_digit(arg, &self.prefixes) {
+            return true;
+        }
+        config.negative_numbers && is_negative_number(arg, &self.prefixes) 
+            && ! self.short_map.contains_key(&arg[self.prefixes.short.len()..])
+    }
+    
+    /// Returns whether the argument is an unknown long option that should be
+    /// added to the trail as it is, rather than failing the parse.
+    fn passes_to_trail(&self, arg: &str, config: &ParseConfig<'def>) -> bool {
+        config.unknown_long_to_trail && self.trail.is_some() && ! self.is_known_option(arg)
+            && match self.prefixes.split(arg) {
+                Some((true, name)) => ! name.is_empty(),
+                _ => false,
+            }
+    }
+    
+    /// Checks a value against the bounds and choices of the trail, parses it
+    /// into the trail (or defers it), and marks the trail as given. Does nothing if 
+    /// there is no trail.
+    fn add_to_trail(&mut self, value: &str, help: Rc<Help<'def>>) -> Result<(), ParseError<'def>> {
+        if let Some((ref name, ref mut satisfied, ref mut target, _)) = self.trail {
+            if let Some((_, Some(max))) = self.trail_bounds {
+                if self.trail_count == max {
+                    return ParseError::parse(ParseFailure::new(FailureKind::UnexpectedArgument, 
+                        format!("Unexpected argument '{}' (at most {} values are expected for '{}')", 
+                            value, max, name))
+                        .option(name.as_ref()).value(value), help);
+                }
+            }
+            self.trail_count += 1;
+            let given = value;
+            let value = match self.choices.get(name) {
+                Some(&(ref choices, ignore_case)) => {
+                    let matches = |choice: &&Cow<str>| if ignore_case {
+                        choice.to_lowercase() == given.to_lowercase()
+                    } else {
+                        *choice == given
+                    };
+                    match choices.iter().find(matches) {
+                        Some(choice) => choice.as_ref(),
+                        None => return ParseError::parse(ParseFailure::new(FailureKind::BadValue, 
+                            format!("Invalid value '{}' for '{}' (expected one of: {})", 
+                                given, name, choices.join(", ")))
+                            .option(name.as_ref()).value(given), help),
+                    }
+                }
+                None => given,
+            };
+            self.effective_values.push(value.to_string());
+            if let Some(ref mut deferred) = self.deferred {
+                deferred.push(Deferred::Trail(value.to_string()));
+            } else if let Err(msg) = target.parse_and_add(value) {
+                return ParseError::parse(ParseFailure::new(FailureKind::BadValue, msg)
+                    .option(name.as_ref()).value(value), help); // TODO: chain err
+            }
+            *satisfied = true;
+        }
+        Ok(())
+    }
+    
+    /// Reads the given option, and takes its value from `args` if it needs one.
+    ///
+    /// The token following a setting or collector is ALWAYS used as its value,
+    /// even when it looks like an option or is the `--` separator, so
+    /// `--opt --` sets `opt` to `"--"`. Separators and options are only
+    /// recognized where an option is expected, never where a value is.
+    ///
+    /// The exceptions are greedy settings, which take every argument until the
+    /// next known option or `--`, and variadic collectors, which take every 
+    /// argument until the next one starting with a dash.
+    ///
+    /// A long setting or collector can also be given its value attached, like
+    /// `--opt=value`. The attached value is used as is, even when it is empty
+    /// (`--opt=`) or the setting is greedy.
+    ///
+    /// Panics in interrupt callbacks are propagated, unless the config says
+    /// to catch them, in which case they fail the parse instead.
+    ///
+    /// Returns the name of the option, and the exit code if it interrupted
+    /// the parse. An interrupt that lets the parse continue is given like a
+    /// flag.
+    fn read_option<'arg, I>(&mut self, option: &str, args: &mut Peekable<I>, 
+        given_values: &mut HashSet<Cow<'def, str>>, help: Rc<Help<'def>>, 
+        config: &ParseConfig<'def>) 
+        -> Result<(Cow<'def, str>, Option<i32>), ParseError<'def>>
+      where I: Iterator<Item=&'arg str> + Clone
     {
         use self::TargetRef::*;
-        match self.get_target(option, help.clone())? {
-            (_, &mut Flag(ref mut target)) => {
-                **target = true;
+        let (option, attached) = self.prefixes.split_attached(option);
+        let name = self.get_name(option, help.clone())?;
+        let negated = match self.prefixes.split(option) {
+            Some((true, key)) => match self.match_long(key) {
+                Ok(Some(key)) => self.negations.contains_key(key),
+                _ => false,
+            },
+            _ => false,
+        };
+        let greedy_value = match self.options[&name] {
+            Setting { greedy: true, .. } if attached.is_none() => self.read_greedy(args),
+            Setting { .. } | Collect { .. } | Features(_) => None,
+            _ if attached.is_some() => {
+                return ParseError::parse(ParseFailure::new(FailureKind::UnexpectedValue, 
+                    format!("Option '{}' does not take a value", option))
+                    .option(option).value(attached.unwrap_or("")), help);
             }
-            (_, &mut Count(ref mut target)) => {
-                **target += 1;
+            _ => None,
+        };
+        let target = self.options.get_mut(&name).unwrap();
+        match *target {
+            Flag(ref mut target) => {
+                // With '+' toggles, the dash forms disable the flag.
+                if ! self.dry_run {
+                    **target = ! config.plus_toggles;
+                }
+                self.effective_options.push(format!("{}{}", self.prefixes.long, name));
+            }
+            Count(ref mut target) => {
+                if ! self.dry_run {
+                    **target += 1;
+                    if let Some(&mut (threshold, ref mut callback)) = self.thresholds.get_mut(&name) {
+                        if **target == threshold {
+                            callback();
+                        }
+                    }
+                }
+                self.effective_options.push(format!("{}{}", self.prefixes.long, name));
+            }
+            Features(ref mut target) => {
+                let feature = if let Some(feature) = attached.or_else(|| args.next()) {
+                    feature
+                } else {
+                    return ParseError::parse(ParseFailure::new(FailureKind::MissingValue, 
+                        format!("Missing argument for option '{}'", option)).option(option), help);
+                };
+                let negation = if negated { "no-" } else { "" };
+                self.effective_options.push(format!("{}{}{}={}", self.prefixes.long, negation, name, feature));
+                if ! self.dry_run {
+                    target.insert(feature.to_string(), ! negated);
+                }
             }
-            (ref name, &mut Setting(ref mut target)) => {
-                if given_values.contains(name) {
-                    return ParseError::parse(format!("Option '{}' given twice!", name), help);
+            Setting { ref mut target, greedy, overridable, ref mut presence, file_indirect } => {
+                if given_values.contains(&name) && ! overridable {
+                    return ParseError::parse(ParseFailure::new(FailureKind::DuplicateOption, 
+                        format!("Option '{}' given twice!", name)).option(option), help);
                 }
-                let arg = if let Some(arg) = args.next() {
+                let arg = if let Some(value) = attached {
+                    Some(value.to_string())
+                } else if greedy {
+                    greedy_value
+                } else {
+                    args.next().map(|arg| arg.to_string())
+                };
+                let arg = if let Some(arg) = arg {
                     arg
                 } else {
-                    return ParseError::parse(format!("Missing argument for option '{}'", option), help);
+                    return ParseError::parse(ParseFailure::new(FailureKind::MissingValue, 
+                        format!("Missing argument for option '{}'", option)).option(option), help);
                 };
-                match target.parse(arg) {
-                    Ok(_) => {}
-                    Err(msg) => return ParseError::parse(msg, help),
+                self.effective_options.push(format!("{}{}={}", self.prefixes.long, name, arg));
+                let arg = if file_indirect {
+                    match read_indirect(arg) {
+                        Ok(arg) => arg,
+                        Err((msg, arg)) => return ParseError::parse(ParseFailure::new(FailureKind::BadValue, msg)
+                            .option(option).value(arg), help),
+                    }
+                } else {
+                    arg
                 };
-                given_values.insert(name.clone());
+                let arg = if self.trimmed.contains(&name) { arg.trim().to_string() } else { arg };
+                if arg.is_empty() && self.non_empty.contains(&name) {
+                    return ParseError::parse(ParseFailure::new(FailureKind::BadValue, 
+                        format!("Argument '{}' must not be empty", name)).option(option).value(arg), help);
+                }
+                if let Some(Err(msg)) = self.signs.get(&name).map(|sign| sign.check(&name, &arg)) {
+                    return ParseError::parse(ParseFailure::new(FailureKind::BadValue, msg)
+                        .option(option).value(arg), help);
+                }
+                self.context.insert(name.as_ref(), arg.as_str());
+                if self.templated.contains(&name) {
+                    // Parsed once the templates are filled in.
+                } else if let Some(ref mut deferred) = self.deferred {
+                    deferred.push(Deferred::Option(name.clone(), option.to_string(), arg));
+                } else if let Err(msg) = target.parse(&arg) {
+                    return ParseError::parse(ParseFailure::new(FailureKind::BadValue, msg)
+                        .option(option).value(arg), help);
+                }
+                if let Some(ref mut presence) = *presence {
+                    if ! self.dry_run {
+                        **presence = true;
+                    }
+                }
             }
-            (_, &mut Collect(ref mut collection_target)) => {
-                let arg = if let Some(arg) = args.next() {
-                    arg
+            Collect { target: ref mut collection_target, variadic, delimiter, ref mut counter, key_separator, 
+                    ref terminator } => {
+                let values = if let Some((ref terminator, required)) = *terminator {
+                    let mut values = attached.into_iter().collect::<Vec<_>>();
+                    let mut terminated = false;
+                    for arg in args.by_ref() {
+                        if arg == terminator {
+                            terminated = true;
+                            break;
+                        }
+                        values.push(arg);
+                    }
+                    if ! terminated && required {
+                        return ParseError::parse(ParseFailure::new(FailureKind::MissingValue, 
+                            format!("Missing '{}' to end the arguments of option '{}'", terminator, option))
+                            .option(option), help);
+                    }
+                    self.effective_options.push(format!("{}{}", self.prefixes.long, name));
+                    self.effective_options.extend(values.iter().map(|arg| arg.to_string()));
+                    self.effective_options.push(terminator.to_string());
+                    values
                 } else {
-                    return ParseError::parse(format!("Missing argument for option '{}'", option), help);
+                    let arg = if let Some(arg) = attached.or_else(|| args.next()) {
+                        arg
+                    } else {
+                        return ParseError::parse(ParseFailure::new(FailureKind::MissingValue, 
+                            format!("Missing argument for option '{}'", option)).option(option), help);
+                    };
+                    let mut values = vec![arg];
+                    if variadic && attached.is_none() {
+                        while let Some(&arg) = args.peek() {
+                            if self.prefixes.split(arg).is_some() {
+                                break;
+                            }
+                            values.push(arg);
+                            args.next();
+                        }
+                    }
+                    for arg in &values {
+                        self.effective_options.push(format!("{}{}={}", self.prefixes.long, name, arg));
+                    }
+                    values
                 };
-                match collection_target.parse_and_add(arg) {
-                    Ok(_) => {}
-                    Err(msg) => return ParseError::parse(msg, help),
+                for arg in split_values(values, delimiter) {
+                    let arg = trim_if(&self.trimmed, &name, arg);
+                    if arg.is_empty() && self.lists.contains(&name) {
+                        continue;
+                    }
+                    if let Some(seen) = self.unique.get_mut(&name) {
+                        if ! seen.insert(arg.to_string()) {
+                            return ParseError::parse(ParseFailure::new(FailureKind::BadValue, 
+                                format!("Duplicate value '{}' for '{}'", arg, option))
+                                .option(option).value(arg), help);
+                        }
+                    }
+                    if let Some(ref mut deferred) = self.deferred {
+                        deferred.push(Deferred::Option(name.clone(), option.to_string(), arg.to_string()));
+                        continue;
+                    }
+                    match add_collected(&mut **collection_target, arg, key_separator) {
+                        Ok(_) => {}
+                        Err(msg) => return ParseError::parse(ParseFailure::new(FailureKind::BadValue, msg)
+                            .option(option).value(arg), help),
+                    };
+                    if let Some(ref mut counter) = *counter {
+                        **counter += 1;
+                    }
+                }
+            }
+            Interrupt { ref mut callback, exit_code } => {
+                if self.dry_run {
+                    return Ok((name, Some(exit_code)));
+                }
+                // The rest is copied for the callback, as the parse may continue.
+                let rest = args.clone().collect::<Vec<_>>();
+                let flow = if config.catch_interrupt_panics {
+                    match panic::catch_unwind(AssertUnwindSafe(|| callback(help.clone(), option, &rest))) {
+                        Ok(flow) => flow,
+                        Err(payload) => {
+                            let reason = match payload.downcast_ref::<&str>() {
+                                Some(reason) => Some(reason.to_string()),
+                                None => payload.downcast_ref::<String>().cloned(),
+                            };
+                            let msg = match reason {
+                                Some(reason) => format!("The interrupt handler for '{}' failed: {}", option, reason),
+                                None => format!("The interrupt handler for '{}' failed", option),
+                            };
+                            return ParseError::parse(ParseFailure::new(FailureKind::InterruptPanicked, msg)
+                                .option(option), help);
+                        }
+                    }
+                } else {
+                    callback(help, option, &rest)
                 };
+                if flow == InterruptFlow::Abort {
+                    return Ok((name, Some(exit_code)));
+                }
+                self.effective_options.push(format!("{}{}", self.prefixes.long, name));
             }
-            (ref name, &mut Interrupt(ref mut callback)) => {
-                callback(help);
-                return Ok(Some(name.clone()));
+        }
+        given_values.insert(name.clone());
+        Ok((name, None))
+    }
+    
+    /// Reads the eager options in the arguments, skipping the values of the
+    /// other options. Returns the interrupt, if an eager one aborts the parse.
+    fn read_eager<'arg>(&mut self, args: &[&'arg str], given_values: &mut HashSet<Cow<'def, str>>, 
+            help: Rc<Help<'def>>, config: &ParseConfig<'def>) 
+            -> Result<Option<(Cow<'def, str>, i32)>, ParseError<'def>> {
+        self.eager_read = true;
+        let mut index = 0;
+        while index < args.len() {
+            let arg = args[index];
+            index += 1;
+            if arg == self.prefixes.long {
+                break;
+            }
+            if self.prefixes.split(arg).is_none() || self.is_value(arg, config) {
+                continue;
+            }
+            let (option, attached) = self.prefixes.split_attached(arg);
+            let name = match self.get_name(option, help.clone()) {
+                Ok(name) => name,
+                // Reported when the arguments are read in order.
+                Err(_) => continue,
+            };
+            if self.eager.contains(&name) {
+                let mut rest = args[index..].iter().cloned().peekable();
+                let (name, interrupt) = self.read_option(arg, &mut rest, given_values, help.clone(), config)?;
+                if let Some(exit_code) = interrupt {
+                    return Ok(Some((name, exit_code)));
+                }
+            } else if attached.is_none() {
+                index = self.skip_values(&name, args, index);
             }
         }
         Ok(None)
     }
+    
+    /// Returns the index after the values of the option, when they aren't
+    /// attached to it and start at the given index.
+    fn skip_values(&self, name: &str, args: &[&str], mut index: usize) -> usize {
+        use self::TargetRef::*;
+        match self.options[name] {
+            Setting { greedy: false, .. } | Features(_) | Collect { terminator: None, .. } => index += 1,
+            Collect { terminator: Some((ref terminator, _)), .. } => {
+                while index < args.len() && args[index] != terminator {
+                    index += 1;
+                }
+                index += 1;
+            }
+            _ => {}
+        }
+        index
+    }
+    
+    /// Finds the value given to the named option, skipping the values of the
+    /// other options like `read_eager`. Options are matched like in the 
+    /// parse, so abbreviations and ignored case apply. When the option is
+    /// given more than once, the last value is returned.
+    fn find_value<'arg>(&self, args: &[&'arg str], target: &str, help: Rc<Help<'def>>, 
+            config: &ParseConfig<'def>) -> Option<&'arg str> {
+        let mut value = None;
+        let mut index = 0;
+        while index < args.len() {
+            let arg = args[index];
+            index += 1;
+            if arg == self.prefixes.long {
+                break;
+            }
+            if self.prefixes.split(arg).is_none() || self.is_value(arg, config) {
+                continue;
+            }
+            let (option, attached) = self.prefixes.split_attached(arg);
+            let name = match self.get_name(option, help.clone()) {
+                Ok(name) => name,
+                // Reported when the arguments are read in order.
+                Err(_) => continue,
+            };
+            if name == target {
+                value = attached.or_else(|| args.get(index).cloned());
+                if attached.is_none() {
+                    index += 1;
+                }
+            } else if attached.is_none() {
+                index = self.skip_values(&name, args, index);
+            }
+        }
+        value
+    }
+    
+    /// Returns the name of the option, if it is eager and has already been 
+    /// read.
+    fn eager_name(&self, arg: &str, help: Rc<Help<'def>>) -> Option<Cow<'def, str>> {
+        if ! self.eager_read {
+            return None;
+        }
+        match self.get_name(self.prefixes.split_attached(arg).0, help) {
+            Ok(ref name) if self.eager.contains(name) => Some(name.clone()),
+            _ => None,
+        }
+    }
+    
+    /// Reads a `+name` toggle, which enables the flag with the given long name,
+    /// or if there is none, the given short identifier. Returns the name of 
+    /// the flag.
+    fn read_toggle(&mut self, toggle: &str, given_values: &mut HashSet<Cow<'def, str>>, 
+            help: Rc<Help<'def>>) -> Result<Cow<'def, str>, ParseError<'def>> {
+        let key = &toggle[1..];
+        let name = if self.options.contains_key(key) {
+            self.get_interned_name(key)
+        } else if let Some(name) = self.short_map.get(key) {
+            name.clone()
+        } else {
+            return ParseError::parse(ParseFailure::new(FailureKind::UnknownOption, 
+                format!("Unknown option '{}'", toggle)).option(toggle), help);
+        };
+        match self.options.get_mut(&name) {
+            Some(&mut TargetRef::Flag(ref mut target)) => {
+                if ! self.dry_run {
+                    **target = true;
+                }
+                self.effective_options.push(format!("+{}", name));
+            }
+            _ => {
+                return ParseError::parse(ParseFailure::new(FailureKind::UnexpectedValue,
+                    format!("Only flags can be enabled with '+', not '{}'", toggle))
+                    .option(toggle), help);
+            }
+        }
+        given_values.insert(name.clone());
+        Ok(name)
+    }
+    
+    /// Assigns the default values of the config to the options that were not
+    /// given, in the order of their names.
+    fn apply_defaults(&mut self, config: &ParseConfig<'def>, 
+            given_values: &HashSet<Cow<'def, str>>, help: Rc<Help<'def>>) 
+            -> Result<(), ParseError<'def>> {
+        use self::TargetRef::*;
+        let mut defaults = config.defaults.iter().collect::<Vec<_>>();
+        defaults.sort();
+        for (name, value) in defaults {
+            if given_values.contains(name.as_str()) {
+                continue;
+            }
+            let long = &self.prefixes.long;
+            let effective = &mut self.effective_options;
+            let trimmed = &self.trimmed;
+            let list = self.lists.contains(name.as_str());
+            let result = match self.options.get_mut(name.as_str()) {
+                Some(&mut Flag(ref mut target)) => {
+                    parse_value(value).map(|value| {
+                        **target = value;
+                        // With '+' toggles, the dash forms disable the flag.
+                        match (value, config.plus_toggles) {
+                            (true, true) => effective.push(format!("+{}", name)),
+                            (true, false) | (false, true) => effective.push(format!("{}{}", long, name)),
+                            (false, false) => {}
+                        }
+                    })
+                }
+                Some(&mut Count(ref mut target)) => {
+                    parse_value(value).map(|value| {
+                        **target = value;
+                        for _ in 0..value {
+                            effective.push(format!("{}{}", long, name));
+                        }
+                    })
+                }
+                Some(&mut Setting { ref mut target, .. }) => {
+                    let value = trim_if(trimmed, name, value);
+                    effective.push(format!("{}{}={}", long, name, value));
+                    self.context.insert(name.as_str(), value);
+                    if self.templated.iter().any(|templated| templated == name) {
+                        Ok(())
+                    } else {
+                        target.parse(value)
+                    }
+                }
+                Some(&mut Features(ref mut target)) => {
+                    effective.push(format!("{}{}={}", long, name, value));
+                    target.insert(value.clone(), true);
+                    Ok(())
+                }
+                Some(&mut Collect { ref mut target, delimiter, ref mut counter, key_separator, .. }) => {
+                    effective.push(format!("{}{}={}", long, name, value));
+                    split_values(vec![value], delimiter).into_iter()
+                        .map(|value| trim_if(trimmed, name, value))
+                        .filter(|value| ! (list && value.is_empty()))
+                        .try_for_each(|value| {
+                            add_collected(&mut **target, value, key_separator)?;
+                            if let Some(ref mut counter) = *counter {
+                                **counter += 1;
+                            }
+                            Ok(())
+                        })
+                }
+                Some(&mut Interrupt { .. }) => {
+                    Err(format!("Interrupt '{}' cannot have a default value", name))
+                }
+                None => Err(format!("Unknown option '{}' in defaults", name)),
+            };
+            if let Err(msg) = result {
+                return ParseError::parse(ParseFailure::new(FailureKind::BadValue, 
+                    format!("Invalid default for '{}': {}", name, msg))
+                    .option(format!("{}{}", self.prefixes.long, name)).value(value.as_str()), help);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Sets the flags that weren't given from their environment variables,
+    /// when those are set.
+    fn apply_env_flags(&mut self, config: &ParseConfig<'def>, 
+            given_values: &HashSet<Cow<'def, str>>, help: Rc<Help<'def>>) 
+            -> Result<(), ParseError<'def>> {
+        for (name, var) in &self.env_flags {
+            if given_values.contains(name) {
+                continue;
+            }
+            let value = match env::var(var.as_ref()) {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+            let option = format!("{}{}", self.prefixes.long, name);
+            let enabled = match truthiness(&value) {
+                Some(enabled) => enabled,
+                None => {
+                    return ParseError::parse(ParseFailure::new(FailureKind::BadValue, 
+                        format!("Invalid value '{}' in '{}' for '{}' (expected 1, true, yes, on, 0, false, no or off)", 
+                            value, var, option)).option(option.as_str()).value(value.as_str()), help);
+                }
+            };
+            if let Some(&mut TargetRef::Flag(ref mut target)) = self.options.get_mut(name) {
+                if ! self.dry_run {
+                    **target = enabled;
+                }
+            }
+            // With '+' toggles, the dash forms disable the flag.
+            match (enabled, config.plus_toggles) {
+                (true, true) => self.effective_options.push(format!("+{}", name)),
+                (true, false) | (false, true) => self.effective_options.push(option),
+                (false, false) => {}
+            }
+        }
+        Ok(())
+    }
+    
+    /// Gives the positionals that weren't given their defaults, when all of
+    /// them have one. Otherwise they are left to fail the parse as missing.
+    fn apply_positional_defaults(&mut self, help: Rc<Help<'def>>) -> Result<(), ParseError<'def>> {
+        if ! self.positional.iter().all(|(name, _)| self.positional_defaults.contains_key(name)) {
+            return Ok(());
+        }
+        while let Some((name, target)) = self.positional.pop_front() {
+            // INVARIANT: Every remaining positional has a default
+            let value = self.positional_defaults[&name].clone();
+            self.effective_values.push(value.to_string());
+            self.context.insert(name.as_ref(), value.as_ref());
+            if let Some(ref mut deferred) = self.deferred {
+                deferred.push(Deferred::Positional(name, target, value.to_string()));
+            } else if let Err(msg) = target.parse(&value) {
+                return ParseError::parse(ParseFailure::new(FailureKind::BadValue, 
+                    format!("Invalid default for '{}': {}", name, msg)).option(name.as_ref()).value(value.as_ref()), help);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Computes the defaults of the settings that weren't given and didn't
+    /// get a default from the config, in definition order.
+    fn apply_computed_defaults(&mut self, config: &ParseConfig<'def>, 
+            given_values: &HashSet<Cow<'def, str>>, help: Rc<Help<'def>>) 
+            -> Result<(), ParseError<'def>> {
+        if self.dry_run {
+            return Ok(());
+        }
+        for &mut (ref name, ref mut callback) in &mut self.computed_defaults {
+            if given_values.contains(name) || config.defaults.contains_key(name.as_ref()) {
+                continue;
+            }
+            let value = callback(&self.context);
+            if self.templated.contains(name) {
+                // Parsed once the templates are filled in.
+            } else if let Some(&mut TargetRef::Setting { ref mut target, .. }) = self.options.get_mut(name) {
+                if let Err(msg) = target.parse(&value) {
+                    return ParseError::parse(ParseFailure::new(FailureKind::BadValue, 
+                        format!("Invalid default for '{}': {}", name, msg))
+                        .option(format!("{}{}", self.prefixes.long, name)).value(value), help);
+                }
+            }
+            self.effective_options.push(format!("{}{}={}", self.prefixes.long, name, value));
+            self.context.insert(name.as_ref(), value);
+        }
+        Ok(())
+    }
+    
+    /// Fills in the placeholders of the templated settings that have values,
+    /// and parses them.
+    fn apply_templates(&mut self, help: Rc<Help<'def>>) -> Result<(), ParseError<'def>> {
+        use self::TargetRef::*;
+        let mut resolved = HashMap::new();
+        for name in &self.templated {
+            if self.context.get(name).is_none() {
+                continue;
+            }
+            let option = format!("{}{}", self.prefixes.long, name);
+            let value = match resolve_template(name, &self.context, &self.templated, &mut resolved, &mut Vec::new()) {
+                Ok(value) => value,
+                Err(msg) => return ParseError::parse(ParseFailure::new(FailureKind::BadValue, 
+                    format!("Invalid template for '{}': {}", option, msg))
+                    .option(option.as_str()).value(self.context.get(name).unwrap_or("")), help),
+            };
+            if self.dry_run {
+                continue;
+            }
+            if let Some(&mut Setting { ref mut target, .. }) = self.options.get_mut(name) {
+                if let Err(msg) = target.parse(&value) {
+                    return ParseError::parse(ParseFailure::new(FailureKind::BadValue, msg)
+                        .option(option).value(value), help);
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    /// Sets the flags implied by the given options, until no more are implied.
+    fn apply_implications(&mut self, given_values: &mut HashSet<Cow<'def, str>>) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (option, implied) in &self.implies {
+                let active = match self.options.get(option) {
+                    Some(TargetRef::Flag(target)) => **target || self.dry_run,
+                    _ => true,
+                };
+                if given_values.contains(option) && active && ! given_values.contains(implied) {
+                    if let Some(&mut TargetRef::Flag(ref mut target)) = self.options.get_mut(implied) {
+                        if ! self.dry_run {
+                            **target = true;
+                        }
+                    }
+                    given_values.insert(implied.clone());
+                    changed = true;
+                }
+            }
+        }
+    }
+    
+    /// Checks that every required option was given, has a default, or is a
+    /// flag with its environment variable set.
+    fn check_required(&self, given_values: &HashSet<Cow<'def, str>>, 
+            defaults: &HashMap<String, String>, help: Rc<Help<'def>>) 
+            -> Result<(), ParseError<'def>> {
+        for (name, short) in &self.required {
+            if given_values.contains(name) || defaults.contains_key(name.as_ref()) {
+                continue;
+            }
+            if self.env_flags.iter().any(|(flag, var)| flag == name && env::var(var.as_ref()).is_ok()) {
+                continue;
+            }
+            let long = format!("{}{}", self.prefixes.long, name);
+            let flags = match *short {
+                Some(ref short) => format!("{} ({}{})", long, self.prefixes.short, short),
+                None => long.clone(),
+            };
+            return ParseError::parse(ParseFailure::new(FailureKind::MissingOption, 
+                format!("Missing required option: {}", flags)).option(long), help);
+        }
+        Ok(())
+    }
+    
+    /// Checks that the requirements and conflicts of the given options hold.
+    fn check_relations(&self, given_values: &HashSet<Cow<'def, str>>, help: Rc<Help<'def>>) 
+            -> Result<(), ParseError<'def>> {
+        let long = &self.prefixes.long;
+        for (option, required) in &self.requires {
+            if given_values.contains(option) && ! given_values.contains(required) {
+                return ParseError::parse(ParseFailure::new(FailureKind::MissingDependency, 
+                    format!("Option '{}{}' requires '{}{}'", long, option, long, required))
+                    .option(format!("{}{}", long, option)), help);
+            }
+        }
+        for (option, other) in &self.conflicts {
+            if given_values.contains(option) && given_values.contains(other) {
+                return ParseError::parse(ParseFailure::new(FailureKind::ConflictingOptions, 
+                    format!("Options '{}{}' and '{}{}' cannot be used together", long, option, long, other))
+                    .option(format!("{}{}", long, option)), help);
+            }
+        }
+        for (option, other) in &self.together {
+            if given_values.contains(option) != given_values.contains(other) {
+                let given = if given_values.contains(option) { option } else { other };
+                return ParseError::parse(ParseFailure::new(FailureKind::MissingDependency, 
+                    format!("Options '{}{}' and '{}{}' must be used together", long, option, long, other))
+                    .option(format!("{}{}", long, given)), help);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Parses the deferred values into their targets, in the order they were
+    /// given.
+    fn parse_deferred(&mut self, help: Rc<Help<'def>>) -> Result<(), ParseError<'def>> {
+        use self::TargetRef::*;
+        let deferred = match self.deferred.take() {
+            Some(_) if self.dry_run => return Ok(()),
+            Some(deferred) => deferred,
+            None => return Ok(()),
+        };
+        for value in deferred {
+            let result = match value {
+                Deferred::Positional(name, target, value) => {
+                    target.parse(&value).map_err(|msg| ParseFailure::new(FailureKind::BadValue, msg)
+                        .option(name.as_ref()).value(value))
+                }
+                Deferred::Trail(value) => {
+                    // INVARIANT: Trail values are only deferred when there is a trail
+                    let (ref name, _, ref mut target, _) = *self.trail.as_mut().unwrap();
+                    target.parse_and_add(&value).map_err(|msg| ParseFailure::new(FailureKind::BadValue, msg)
+                        .option(name.as_ref()).value(value))
+                }
+                Deferred::Option(name, option, value) => {
+                    let result = match self.options.get_mut(&name) {
+                        Some(&mut Setting { ref mut target, .. }) => target.parse(&value),
+                        Some(&mut Collect { ref mut target, ref mut counter, key_separator, .. }) => {
+                            add_collected(&mut **target, &value, key_separator).map(|_| {
+                                if let Some(ref mut counter) = *counter {
+                                    **counter += 1;
+                                }
+                            })
+                        }
+                        _ => Ok(()),
+                    };
+                    result.map_err(|msg| ParseFailure::new(FailureKind::BadValue, msg)
+                        .option(option).value(value))
+                }
+            };
+            if let Err(failure) = result {
+                return ParseError::parse(failure, help);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Returns the values of the given flags, by name.
+    fn flag_values(&self, given_values: &HashSet<Cow<'def, str>>) -> HashMap<Cow<'def, str>, bool> {
+        given_values.iter().filter_map(|name| match self.options.get(name) {
+            Some(TargetRef::Flag(target)) => Some((name.clone(), **target)),
+            _ => None,
+        }).collect()
+    }
+}
+
+/// Reads the value from a file if it is given as `@path` (and `@@` stands for 
+/// a literal `@`). Errors come with the value as it was given.
+fn read_indirect(value: String) -> Result<String, (String, String)> {
+    if value.starts_with("@@") {
+        return Ok(value[1..].to_string());
+    } else if ! value.starts_with("@") {
+        return Ok(value);
+    }
+    let mut contents = String::new();
+    match File::open(&value[1..]).and_then(|mut file| file.read_to_string(&mut contents)) {
+        Ok(_) => Ok(contents.trim().to_string()),
+        Err(e) => Err((format!("Could not read '{}': {}", &value[1..], e), value)),
+    }
+}
+
+/// Fails if more positional values have been given than the config allows.
+fn check_positional_count<'def>(config: &ParseConfig<'def>, count: usize, arg: &str, help: Rc<Help<'def>>) 
+        -> Result<(), ParseError<'def>> {
+    match config.max_positionals {
+        Some(max) if count > max => ParseError::parse(ParseFailure::new(FailureKind::TooManyArguments, 
+            format!("Too many positional arguments (max {}): '{}'", max, arg)).value(arg), help),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the number of single character insertions, deletions and 
+/// substitutions that turn one string into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..b.len() + 1).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == cb { 0 } else { 1 };
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Fails if the required positional is defined after an optional one, as it
+/// could never be given without it.
+fn check_required_positional(name: &str, positional: &VecDeque<(Cow<str>, &mut SingleTarget)>, 
+        defaults: &HashMap<Cow<str>, Cow<str>>) -> Result<(), ParseError<'static>> {
+    match positional.iter().find(|&(other, _)| defaults.contains_key(other)) {
+        Some((optional, _)) => ParseError::defs(format!(
+            "The required positional '{}' is defined after the optional positional '{}'.", name, optional)),
+        None => Ok(()),
+    }
+}
+
+/// Reads the value of an environment variable as a boolean, if it is one.
+pub(crate) fn truthiness(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" | "" => Some(false),
+        _ => None,
+    }
+}
+
+/// Removes the whitespace around the value, if the argument is trimmed.
+fn trim_if<'a>(trimmed: &HashSet<Cow<str>>, name: &str, value: &'a str) -> &'a str {
+    if trimmed.contains(name) { value.trim() } else { value }
+}
+
+/// Splits each value on the delimiter, if there is one.
+fn split_values(values: Vec<&str>, delimiter: Option<char>) -> Vec<&str> {
+    match delimiter {
+        Some(delimiter) => values.iter().flat_map(|value| value.split(delimiter)).collect(),
+        None => values,
+    }
+}
+
+/// Adds a collected value to its target, as a key-value pair if the 
+/// collector reads pairs.
+fn add_collected(target: &mut CollectionTarget, value: &str, key_separator: Option<char>) 
+        -> Result<(), String> {
+    match key_separator {
+        Some(separator) => match value.find(separator) {
+            Some(index) => {
+                let (key, value) = (&value[..index], &value[index + separator.len_utf8()..]);
+                target.parse_and_add_pair(key.trim(), value.trim())
+            }
+            None => Err(format!("Malformed pair '{}' (expected a key and a value, separated by '{}')", 
+                value.trim(), separator)),
+        },
+        None => target.parse_and_add(value),
+    }
+}
+
+/// Returns whether the argument is a negative number (`-3`, `-2.5`) that 
+/// looks like a short option, which it only does when the short prefix is `-`.
+fn is_negative_number(arg: &str, prefixes: &Prefixes) -> bool {
+    let number = match prefixes.split(arg) {
+        Some((false, number)) if prefixes.short == "-" => number,
+        _ => return false,
+    };
+    let starts_numeric = match number.chars().next() {
+        Some(c) => c.is_ascii_digit() || c == '.',
+        None => false,
+    };
+    starts_numeric && number.parse::<f64>().is_ok()
+}
+
+/// Fills in the placeholders in the value of a templated setting, filling in
+/// the templates it refers to first.
+fn resolve_template<'def>(name: &str, context: &DefaultContext, templated: &[Cow<'def, str>], 
+        resolved: &mut HashMap<String, String>, stack: &mut Vec<String>) -> Result<String, String> {
+    if let Some(value) = resolved.get(name) {
+        return Ok(value.clone());
+    }
+    if stack.iter().any(|outer| outer == name) {
+        stack.push(name.to_string());
+        return Err(format!("the templates refer back to themselves ({})", stack.join(" -> ")));
+    }
+    let template = match context.get(name) {
+        Some(template) => template,
+        None => return Err(format!("no value for the placeholder '{{{}}}'", name)),
+    };
+    if ! templated.iter().any(|other| other == name) {
+        return Ok(template.to_string());
+    }
+    stack.push(name.to_string());
+    let mut value = String::new();
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.as_str().starts_with('{') => {
+                chars.next();
+                value.push('{');
+            }
+            '}' if chars.as_str().starts_with('}') => {
+                chars.next();
+                value.push('}');
+            }
+            '{' => {
+                let rest = chars.as_str();
+                let end = match rest.find('}') {
+                    Some(end) => end,
+                    None => return Err(format!("unclosed placeholder in '{}'", template)),
+                };
+                value.push_str(&resolve_template(&rest[..end], context, templated, resolved, stack)?);
+                chars = rest[end + 1..].chars();
+            }
+            c => value.push(c),
+        }
+    }
+    stack.pop();
+    resolved.insert(name.to_string(), value.clone());
+    Ok(value)
+}
+
+/// Returns whether the argument is the short prefix followed by a digit.
+fn starts_with_prefix_digit(arg: &str, prefixes: &Prefixes) -> bool {
+    arg.starts_with(prefixes.short.as_str()) 
+        && arg[prefixes.short.len()..].chars().next().is_some_and(|c| c.is_ascii_digit())
 }
 
 fn validate_short<'def, N: AsRef<str>>(name: &N) -> Result<(), ParseError<'def>> {
@@ -131,38 +1243,228 @@ fn add_option<'def, 'tar>(
     Ok(())
 }
 
+/// Warns about definitions that write to the same variable.
+/// 
+/// Safe code cannot do this (the targets are borrowed mutably), but when it 
+/// happens, the last definition parsed silently wins.
+fn warn_shared_targets<'def, 'tar>(defs: &[ArgDef<'def, 'tar>], warnings: &mut Vec<String>) {
+    let mut owners = HashMap::new();
+    for def in defs {
+        if let Some(address) = def.target_address() {
+            if let Some(other) = owners.insert(address, &def.name) {
+                warnings.push(format!("'{}' and '{}' write to the same target (ArgDef error)", other, def.name));
+            }
+        }
+    }
+}
+
+/// Checks that the relations only refer to defined options, and that every
+/// option can be given without its requirements conflicting.
+fn validate_relations<'def, 'tar>(options: &HashMap<Cow<'def, str>, TargetRef<'def, 'tar>>,
+        requires: &[(Cow<'def, str>, Cow<'def, str>)], 
+        conflicts: &[(Cow<'def, str>, Cow<'def, str>)], 
+        implies: &[(Cow<'def, str>, Cow<'def, str>)],
+        together: &[(Cow<'def, str>, Cow<'def, str>)]) 
+        -> Result<(), ParseError<'def>> {
+    for (option, flag) in implies {
+        match options.get(flag) {
+            Some(&TargetRef::Flag(_)) => {}
+            _ => return ParseError::defs(format!("'{}' implies '{}', which is not a defined flag.", option, flag)),
+        }
+    }
+    for (option, other) in requires.iter().chain(conflicts).chain(implies).chain(together) {
+        if ! options.contains_key(other) {
+            return ParseError::defs(format!("'{}' refers to '{}', which is not a defined option.", option, other));
+        }
+        if ! options.contains_key(option) {
+            return ParseError::defs(format!("Only options can require, conflict with, imply or be used together with others, not '{}'.", option));
+        }
+    }
+    // Options used together require each other.
+    let requires = requires.iter().map(|(from, to)| (from, to))
+        .chain(together.iter().flat_map(|(a, b)| vec![(a, b), (b, a)]))
+        .collect::<Vec<_>>();
+    for &(option, _) in &requires {
+        // Everything that must be given along with the option
+        let mut needed = vec![option];
+        let mut i = 0;
+        while i < needed.len() {
+            for &(from, to) in &requires {
+                if from == needed[i] && ! needed.contains(&to) {
+                    needed.push(to);
+                }
+            }
+            i += 1;
+        }
+        for (a, b) in conflicts {
+            if needed.contains(&a) && needed.contains(&b) {
+                return ParseError::defs(format!("Option '{}' can never be given, as it requires '{}' and '{}', which conflict.", 
+                    option, a, b));
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Sorts the given definitions and checks that all invariants are upheld.
-pub fn parse_definitions<'def, 'tar>(defs: Vec<ArgDef<'def, 'tar>>) 
-        -> Result<ParseState<'def, 'tar>, ParseError<'def>> {
+///
+/// Warnings about the definitions are added to `warnings`, also when they 
+/// turn out to be invalid.
+pub fn parse_definitions<'def, 'tar>(defs: Vec<ArgDef<'def, 'tar>>, config: &ParseConfig<'def>, 
+        warnings: &mut Vec<String>) -> Result<ParseState<'def, 'tar>, ParseError<'def>> {
+    for def in &defs {
+        warnings.extend(def.warnings.iter().cloned());
+    }
+    if cfg!(debug_assertions) {
+        warn_shared_targets(&defs, warnings);
+    }
+    let prefixes = config.prefixes.clone();
+    if prefixes.short.is_empty() || prefixes.long.is_empty() || prefixes.short == prefixes.long {
+        return ParseError::defs(format!("Invalid option prefixes '{}' and '{}'. They must be non-empty and different.", 
+            prefixes.short, prefixes.long));
+    }
     let mut positional = VecDeque::new();
     let mut trail = None;
+    let mut trail_bounds = None;
+    let mut command = None;
+    let mut command_index = 0;
+    let mut negations = HashMap::new();
     let mut options = HashMap::new(); // long-to-arg
     let mut short_map = HashMap::new(); // short-to-long
     let mut subcommands = HashMap::new();
     let mut has_positional = false;
     let mut has_subcommand = false;
-    for def in defs {
+    let mut requires = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut implies = Vec::new();
+    let mut together = Vec::new();
+    let mut required = Vec::new();
+    let mut choices = HashMap::new();
+    let mut non_empty = HashSet::new();
+    let mut trimmed = HashSet::new();
+    let mut lists = HashSet::new();
+    let mut signs = HashMap::new();
+    let mut without_subcommand = Vec::new();
+    let mut scopes = Vec::new();
+    let mut unique = HashMap::new();
+    let mut computed_defaults = Vec::new();
+    let mut templated = Vec::new();
+    let mut eager = HashSet::new();
+    let mut thresholds = HashMap::new();
+    let mut env_flags = Vec::new();
+    let mut positional_defaults = HashMap::new();
+    for mut def in defs {
+        // A computed default satisfies the requirement.
+        if def.required && def.default_with.is_none() {
+            required.push((def.name.clone(), def.short_id().map(|short| Cow::Owned(short.to_string()))));
+        }
+        if let Some(default) = def.default_with.take() {
+            computed_defaults.push((def.name.clone(), default));
+        }
+        let default_check = match (&def.default, &def.kind) {
+            (Some(value), ArgDefKind::Setting { target, .. }) => target.check(value),
+            (Some(value), ArgDefKind::Positional { target }) => target.check(value),
+            _ => Ok(()),
+        };
+        if let Err(msg) = default_check {
+            // INVARIANT: Only defaults are checked
+            return ParseError::defs(format!("Invalid default '{}' for '{}': {}", 
+                def.default.unwrap(), def.name, msg));
+        }
+        if let Some(at_count) = def.at_count.take() {
+            thresholds.insert(def.name.clone(), at_count);
+        }
+        if let Some(var) = def.env.take() {
+            env_flags.push((def.name.clone(), var));
+        }
+        requires.extend(def.requires.iter().map(|other| (def.name.clone(), other.clone())));
+        conflicts.extend(def.conflicts.iter().map(|other| (def.name.clone(), other.clone())));
+        implies.extend(def.implies.iter().map(|other| (def.name.clone(), other.clone())));
+        together.extend(def.together.iter().map(|other| (def.name.clone(), other.clone())));
+        if let Some(short) = def.short_id() {
+            if short.chars().count() != 1 && ! def.long_short {
+                return ParseError::defs(format!("Invalid short identifier '{}' for '{}'. Short ids must be a single character, unless allowed with 'long_short'.", 
+                    short, def.name));
+            }
+        }
+        if def.non_empty {
+            non_empty.insert(def.name.clone());
+        }
+        if def.trim {
+            trimmed.insert(def.name.clone());
+        }
+        if def.list {
+            lists.insert(def.name.clone());
+        }
+        if let Some(sign) = def.sign {
+            signs.insert(def.name.clone(), sign);
+        }
+        if def.without_subcommand {
+            without_subcommand.push(def.name.clone());
+        }
+        if ! def.scopes.is_empty() {
+            scopes.push((def.name.clone(), def.scopes.clone()));
+        }
+        if def.unique {
+            unique.insert(def.name.clone(), HashSet::new());
+        }
+        if def.templated {
+            templated.push(def.name.clone());
+        }
+        if def.eager {
+            eager.insert(def.name.clone());
+        }
+        if ! def.choices.is_empty() {
+            choices.insert(def.name.clone(), (def.choices, def.choices_ignore_case));
+        }
         match def.kind {
             ArgDefKind::Positional { target } => {
                 if has_subcommand {
-                    return ParseError::defs(format!("Positional (+trail) and subcommand definitions cannot be used together."));
+                    return ParseError::defs("Positional (+trail) and subcommand definitions cannot be used together.".to_string());
                 }
                 has_positional = true;
+                match def.default {
+                    Some(default) => {
+                        positional_defaults.insert(def.name.clone(), default);
+                    }
+                    None => check_required_positional(&def.name, &positional, &positional_defaults)?,
+                }
                 positional.push_back((def.name, target));
             }
-            ArgDefKind::Trail { optional, target } => {
+            ArgDefKind::Command { target, rest, .. } => {
                 if has_subcommand {
-                    return ParseError::defs(format!("Positional (+trail) and subcommand definitions cannot be used together."));
+                    return ParseError::defs("Positional (+trail) and subcommand definitions cannot be used together.".to_string());
+                }
+                if command.is_some() {
+                    return ParseError::defs("Two commands defined.".to_string());
+                }
+                check_required_positional(&def.name, &positional, &positional_defaults)?;
+                has_positional = true;
+                command_index = positional.len();
+                command = Some((def.name.clone(), rest));
+                positional.push_back((def.name, target));
+            }
+            ArgDefKind::Trail { optional, target, delimiter, bounds } => {
+                if has_subcommand {
+                    return ParseError::defs("Positional (+trail) and subcommand definitions cannot be used together.".to_string());
                 }
                 has_positional = true;
                 if trail.is_some() {
-                    return ParseError::defs(format!("Two trails defined."));
+                    return ParseError::defs("Two trails defined.".to_string());
+                }
+                if let Some((min, Some(max))) = bounds {
+                    if min > max {
+                        return ParseError::defs(format!("Invalid bounds for '{}'. The minimum ({}) is greater than the maximum ({}).", 
+                            def.name, min, max));
+                    }
                 }
-                trail = Some((def.name, optional, target));
+                // The count is checked against the bounds instead.
+                trail = Some((def.name, optional || bounds.is_some(), target, delimiter));
+                trail_bounds = bounds;
             }
             ArgDefKind::Subcommand { handler } => {
                 if has_positional {
-                    return ParseError::defs(format!("Positional (+trail) and subcommand definitions cannot be used together."));
+                    return ParseError::defs("Positional (+trail) and subcommand definitions cannot be used together.".to_string());
                 }
                 has_subcommand = true;
                 if subcommands.contains_key(&def.name) {
@@ -176,18 +1478,96 @@ pub fn parse_definitions<'def, 'tar>(defs: Vec<ArgDef<'def, 'tar>>)
             ArgDefKind::Count { short, target } => {
                 add_option(def.name, short, TargetRef::Count(target), &mut options, &mut short_map)?;
             }
-            ArgDefKind::Collect { short, target, .. } => {
-                add_option(def.name, short, TargetRef::Collect(target), &mut options, &mut short_map)?;
+            ArgDefKind::Features { short, target, .. } => {
+                negations.insert(format!("no-{}", def.name), def.name.clone());
+                add_option(def.name, short, TargetRef::Features(target), &mut options, &mut short_map)?;
             }
-            ArgDefKind::Setting { short, target, .. } => {
-                add_option(def.name, short, TargetRef::Setting(target), &mut options, &mut short_map)?;
+            ArgDefKind::Collect { short, target, variadic, delimiter, counter, key_separator, terminator, .. } => {
+                let target = TargetRef::Collect { target, variadic, delimiter, counter, key_separator, terminator };
+                add_option(def.name, short, target, &mut options, &mut short_map)?;
             }
-            ArgDefKind::Interrupt { short, callback } => {
-                add_option(def.name, short, TargetRef::Interrupt(callback), &mut options, &mut short_map)?;
+            ArgDefKind::Setting { short, target, greedy, overridable, presence, file_indirect, .. } => {
+                let target = TargetRef::Setting { target, greedy, overridable, presence, file_indirect };
+                add_option(def.name, short, target, &mut options, &mut short_map)?;
             }
+            ArgDefKind::Interrupt { short, callback, exit_code } => {
+                add_option(def.name, short, TargetRef::Interrupt { callback, exit_code }, &mut options, &mut short_map)?;
+            }
+        }
+    }
+    if config.negative_numbers && has_positional {
+        for short in short_map.keys() {
+            let option = format!("{}{}", config.prefixes.short, short);
+            if is_negative_number(&option, &config.prefixes) {
+                warnings.push(format!("'{}' will be read as an option, never as a negative positional value (ArgDef error)", option));
+            }
+        }
+    }
+    if config.leading_dash_digit_values {
+        for short in short_map.keys() {
+            if starts_with_prefix_digit(&format!("{}{}", prefixes.short, short), &prefixes) {
+                warnings.push(format!("'{}{}' will be read as a value, never as an option (ArgDef error)", 
+                    prefixes.short, short));
+            }
+        }
+    }
+    if let Some(max) = config.max_positionals {
+        let trail_min = match (trail_bounds, &trail) {
+            (Some((min, _)), _) => min,
+            (None, &Some((_, optional, _, _))) => if optional { 0 } else { 1 },
+            (None, &None) => 0,
+        };
+        let needed = positional.len() - positional_defaults.len() + trail_min;
+        if needed > max {
+            return ParseError::defs(format!("The positional arguments need at least {} values, but at most {} are allowed.", 
+                needed, max));
+        }
+    }
+    if let Some((ref name, _)) = command {
+        if trail.is_some() {
+            return ParseError::defs(format!("A trail cannot be defined along with the command '{}', which takes the rest of the arguments.", name));
+        }
+        if let Some((other, _)) = positional.get(command_index + 1) {
+            return ParseError::defs(format!("Positional '{}' is defined after the command '{}', which takes the rest of the arguments.", 
+                other, name));
+        }
+    }
+    for (option, subcommands_of) in &scopes {
+        for subcommand in subcommands_of {
+            if ! subcommands.contains_key(subcommand) {
+                return ParseError::defs(format!("Option '{}' is scoped to '{}', which is not a defined subcommand.", 
+                    option, subcommand));
+            }
+        }
+    }
+    for negation in negations.keys() {
+        if options.contains_key(negation.as_str()) {
+            return ParseError::defs(format!("Option '{}' defined twice.", negation));
         }
     }
-    Ok(ParseState { positional, trail, subcommands, options, short_map })
+    if config.ignore_option_case {
+        let mut lowercase: HashMap<String, &str> = HashMap::new();
+        let mut names = options.keys().map(|name| name.as_ref())
+            .chain(negations.keys().map(|name| name.as_str())).collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            if let Some(other) = lowercase.insert(name.to_lowercase(), name) {
+                return ParseError::defs(format!("Options '{}' and '{}' only differ by case, which is ignored.", 
+                    other, name));
+            }
+        }
+    }
+    validate_relations(&options, &requires, &conflicts, &implies, &together)?;
+    // The values are not parsed in a dry run, so they are deferred and dropped.
+    let deferred = if config.deferred_parsing || config.dry_run { Some(Vec::new()) } else { None };
+    Ok(ParseState { 
+        positional, trail, trail_bounds, trail_count: 0, command, subcommands, options, short_map, negations, prefixes, 
+        requires, conflicts, implies, together, required, deferred, choices, non_empty, trimmed, lists, signs,
+        unique, without_subcommand, scopes, effective_options: Vec::new(), effective_values: Vec::new(), 
+        dry_run: config.dry_run, computed_defaults, context: DefaultContext::default(),
+        templated, eager, eager_read: false, thresholds, env_flags, positional_defaults, suggestions: config.suggestions,
+        abbreviations: config.abbreviations, ignore_case: config.ignore_option_case,
+    })
 }
 
 /// An error found when parsing arguments.
@@ -197,16 +1577,18 @@ pub enum ParseError<'def> {
     InvalidDefinitions(String),
     
     /// The parse could not finish succesfully.
-    ParseFailed(String, Rc<Help<'def>>),
+    ParseFailed(ParseFailure, Rc<Help<'def>>),
     
     /// A subcommand failed to parse, and has been handled.
     SubParseFailed,
     
-    /// An interrupt-flag with the given name was encountered.
+    /// An interrupt-flag with the given name and exit code was encountered.
+    /// The last field is the interrupt as an option, with the long prefix of
+    /// the parse (like `--help`, or `//help` with `ParseConfig::prefixes`).
     /// 
     /// The variables pointed to by the definitions will not all have been
     /// assigned their expected values.
-    Interrupted(Cow<'def, str>)
+    Interrupted(Cow<'def, str>, i32, String)
 }
 
 impl<'def> ParseError<'def> {
@@ -214,91 +1596,564 @@ impl<'def> ParseError<'def> {
         Err(ParseError::InvalidDefinitions(reason.into()))
     }
     
-    fn parse<T, S: Into<String>>(reason: S, help: Rc<Help<'def>>) -> Result<T, ParseError<'def>> {
+    fn parse<T, F: Into<ParseFailure>>(reason: F, help: Rc<Help<'def>>) -> Result<T, ParseError<'def>> {
         Err(ParseError::ParseFailed(reason.into(), help))
     }
     
-    fn interrupt<T>(name: Cow<'def, str>) -> Result<T, ParseError<'def>> {
-       Err( ParseError::Interrupted(name))
+    fn interrupt<T>((name, exit_code): (Cow<'def, str>, i32), prefixes: &Prefixes) -> Result<T, ParseError<'def>> {
+        let option = format!("{}{}", prefixes.long, name);
+        Err(ParseError::Interrupted(name, exit_code, option))
+    }
+    
+    /// Returns the code the program should exit with after this error.
+    /// 
+    /// This is the exit code of the interrupt for `Interrupted`, and 1 for 
+    /// the other errors.
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            ParseError::Interrupted(_, exit_code, _) => exit_code,
+            _ => 1,
+        }
+    }
+}
+
+impl<'def> fmt::Display for ParseError<'def> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseError::InvalidDefinitions(ref msg) => write!(f, "Invalid definitions: {}", msg),
+            ParseError::ParseFailed(ref failure, _) => write!(f, "{}", failure),
+            ParseError::SubParseFailed => write!(f, "Subcommand parse failed"),
+            ParseError::Interrupted(_, _, ref option) => write!(f, "Interrupted by '{}'", option),
+        }
+    }
+}
+
+impl<'def> ParseError<'def> {
+    /// Describes this error as a JSON object, for machine consumers.
+    ///
+    /// Parse failures are described like in `ParseFailure::to_json`. The 
+    /// other errors use the `error` codes `"invalid_definitions"`, 
+    /// `"subcommand_failed"` and `"interrupted"`.
+    pub fn to_json(&self) -> String {
+        let message = self.to_string();
+        match *self {
+            ParseError::InvalidDefinitions(_) => {
+                json_object(&[("error", Some("invalid_definitions")), ("message", Some(&message))])
+            }
+            ParseError::ParseFailed(ref failure, _) => failure.to_json(),
+            ParseError::SubParseFailed => {
+                json_object(&[("error", Some("subcommand_failed")), ("message", Some(&message))])
+            }
+            ParseError::Interrupted(_, _, ref option) => {
+                json_object(&[("error", Some("interrupted")), ("option", Some(option)), 
+                              ("message", Some(&message))])
+            }
+        }
+    }
+}
+
+/// The kind of problem that made a parse fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// An option that isn't defined was given.
+    UnknownOption,
+    /// A prefix of several long options was given (see 
+    /// `ParseConfig::abbreviations`).
+    AmbiguousOption,
+    /// An option was given without the value it expects.
+    MissingValue,
+    /// A value could not be parsed into its target.
+    BadValue,
+    /// A setting was given more than once.
+    DuplicateOption,
+    /// A value was attached to an option that doesn't take one.
+    UnexpectedValue,
+    /// An argument was given that nothing could take.
+    UnexpectedArgument,
+    /// A positional or trailing argument was not given.
+    MissingArgument,
+    /// A subcommand that isn't defined was given.
+    UnknownSubcommand,
+    /// No subcommand was given.
+    MissingSubcommand,
+    /// An option was given without an option it requires.
+    MissingDependency,
+    /// Two options that conflict were both given.
+    ConflictingOptions,
+    /// A required option was not given.
+    MissingOption,
+    /// An option was given before the positional arguments that must come
+    /// first.
+    MisplacedOption,
+    /// More arguments were given than the config allows.
+    TooManyArguments,
+    /// An argument was not valid UTF-8 (see `parse_os`).
+    InvalidUtf8,
+    /// An interrupt callback panicked (see `ParseConfig::catch_interrupt_panics`).
+    InterruptPanicked,
+    /// Any other problem.
+    Other,
+}
+
+impl FailureKind {
+    /// Returns the name of this kind in snake case, like `"missing_value"`.
+    pub fn code(&self) -> &'static str {
+        match *self {
+            FailureKind::UnknownOption => "unknown_option",
+            FailureKind::AmbiguousOption => "ambiguous_option",
+            FailureKind::MissingValue => "missing_value",
+            FailureKind::BadValue => "bad_value",
+            FailureKind::DuplicateOption => "duplicate_option",
+            FailureKind::UnexpectedValue => "unexpected_value",
+            FailureKind::UnexpectedArgument => "unexpected_argument",
+            FailureKind::MissingArgument => "missing_argument",
+            FailureKind::UnknownSubcommand => "unknown_subcommand",
+            FailureKind::MissingSubcommand => "missing_subcommand",
+            FailureKind::MissingDependency => "missing_dependency",
+            FailureKind::ConflictingOptions => "conflicting_options",
+            FailureKind::MisplacedOption => "misplaced_option",
+            FailureKind::MissingOption => "missing_option",
+            FailureKind::TooManyArguments => "too_many_arguments",
+            FailureKind::InvalidUtf8 => "invalid_utf8",
+            FailureKind::InterruptPanicked => "interrupt_panicked",
+            FailureKind::Other => "other",
+        }
+    }
+}
+
+/// A description of why a parse failed.
+#[derive(Debug, Clone)]
+pub struct ParseFailure {
+    /// The kind of problem.
+    pub kind: FailureKind,
+    /// The option or argument with the problem, as it was given (or named).
+    pub option: Option<String>,
+    /// The offending value, if any.
+    pub value: Option<String>,
+    /// A description of the problem for the user.
+    pub message: String,
+}
+
+impl ParseFailure {
+    /// Creates a new failure of the given kind.
+    pub fn new<S: Into<String>>(kind: FailureKind, message: S) -> ParseFailure {
+        ParseFailure { kind, option: None, value: None, message: message.into() }
+    }
+    
+    /// Sets the option or argument that had the problem.
+    pub fn option<S: Into<String>>(mut self, option: S) -> Self {
+        self.option = Some(option.into());
+        self
+    }
+    
+    /// Sets the value that caused the problem.
+    pub fn value<S: Into<String>>(mut self, value: S) -> Self {
+        self.value = Some(value.into());
+        self
+    }
+    
+    /// Describes this failure as a JSON object, like
+    /// `{"error":"missing_value","option":"--port","message":"..."}`.
+    ///
+    /// The `error` is the code of the kind, and `option` and `value` are 
+    /// left out when they aren't known.
+    pub fn to_json(&self) -> String {
+        json_object(&[
+            ("error", Some(self.kind.code())),
+            ("option", self.option.as_deref()),
+            ("value", self.value.as_deref()),
+            ("message", Some(&self.message)),
+        ])
+    }
+}
+
+impl fmt::Display for ParseFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<String> for ParseFailure {
+    fn from(message: String) -> ParseFailure {
+        ParseFailure::new(FailureKind::Other, message)
+    }
+}
+
+impl<'a> From<&'a str> for ParseFailure {
+    fn from(message: &'a str) -> ParseFailure {
+        ParseFailure::new(FailureKind::Other, message)
     }
 }
 
 /// Parses the given arguments and updates the defined variables with them.
 /// This version does not print usage in the case of parse errors, nor does 
 /// it 'un-propagate' parsing errors.
+///
+/// The first `--` argument ends option parsing, so every argument after it
+/// is read as a positional or trail value, even if it starts with a dash.
+/// This includes any further `--`, which is then just a value like any other.
+/// The first `--` itself is not stored anywhere. A lone `-` is always a 
+/// value, as it usually stands for stdin.
+///
+/// # Example
+/// ```
+/// # use argonaut::{ArgDef, parse_plain};
+/// let (mut verbose, mut file, mut rest) = (false, String::new(), Vec::<String>::new());
+/// parse_plain("prog", &["-v", "--", "-file-", "--", "-x"], vec![
+///     ArgDef::flag("verbose", &mut verbose).short("v"),
+///     ArgDef::positional("file", &mut file),
+///     ArgDef::trail("rest", true, &mut rest),
+/// ]).unwrap();
+/// assert!(verbose);
+/// assert_eq!("-file-", file);
+/// assert_eq!(vec!["--", "-x"], rest);
+///
+/// let mut input = String::new();
+/// parse_plain("prog", &["-"], vec![
+///     ArgDef::positional("input", &mut input),
+/// ]).unwrap();
+/// assert_eq!("-", input);
+///
+/// // Options have also ended for a subcommand after `--`.
+/// let code = parse_plain("prog", &["--", "add", "-x"], vec![
+///     ArgDef::subcommand("add", |_, args| {
+///         assert_eq!(vec!["--", "-x"], args);
+///         Ok(Some(2))
+///     }),
+/// ]).unwrap();
+/// assert_eq!(Some(2), code);
+/// ```
 pub fn parse_plain<'def, 'tar, T, P: Into<String>>(program: P, args: &[T], definitions: Vec<ArgDef<'def, 'tar>>) 
     -> Result<Option<i32>, ParseError<'def>>
   where T: Borrow<str> 
+{ 
+    parse_plain_with(program, args, definitions, ParseConfig::new())
+        .map(|summary| summary.exit_code)
+}
+
+/// Like `parse_plain`, but splits the arguments from a single command line,
+/// like a shell would (see `split_line`).
+///
+/// This is useful for REPLs and tests. Unterminated quotes are reported as 
+/// parse failures.
+pub fn parse_str<'def, 'tar, P: Into<String>>(program: P, line: &str, definitions: Vec<ArgDef<'def, 'tar>>) 
+    -> Result<Option<i32>, ParseError<'def>>
+{
+    let program = program.into();
+    match split_line(line) {
+        Ok(args) => parse_plain(program, &args, definitions),
+        Err(msg) => {
+            let help = Rc::new(Help::new(program, &definitions));
+            ParseError::parse(ParseFailure::new(FailureKind::BadValue, msg).value(line), help)
+        }
+    }
+}
+
+/// Like `parse_plain`, but uses the given configuration, and returns a
+/// summary of the parse when it succeeds.
+pub fn parse_plain_with<'def, 'tar, T, P: Into<String>>(program: P, args: &[T], 
+    definitions: Vec<ArgDef<'def, 'tar>>, mut config: ParseConfig<'def>) 
+    -> Result<ParseSummary<'def>, ParseError<'def>>
+  where T: Borrow<str> 
+{ 
+    parse_configured(program, args, definitions, &mut config, None, None)
+}
+
+/// Like `parse_plain_with`, but stops at the first argument it can't read,
+/// and returns it along with every argument after it.
+///
+/// An argument can't be read when it is an unknown option, or a positional 
+/// value when there are no more positional arguments, no trail and no 
+/// subcommand with that name. The remainder can then be handed to another 
+/// parser. Missing positional arguments still make the parse fail.
+pub fn parse_partial<'def, 'tar, T, P: Into<String>>(program: P, args: &[T], 
+    definitions: Vec<ArgDef<'def, 'tar>>, mut config: ParseConfig<'def>) 
+    -> Result<(ParseSummary<'def>, Vec<String>), ParseError<'def>>
+  where T: Borrow<str> 
+{ 
+    let mut rest = Vec::new();
+    let summary = parse_configured(program, args, definitions, &mut config, Some(&mut rest), None)?;
+    Ok((summary, rest))
+}
+
+/// Runs the parse, leaving the configuration available to the caller.
+/// 
+/// If a place for the rest is given, the parse stops at the first argument
+/// it can't read, and moves the remaining arguments there.
+fn parse_configured<'def, 'tar, T, P: Into<String>>(program: P, args: &[T], 
+    definitions: Vec<ArgDef<'def, 'tar>>, config: &mut ParseConfig<'def>, 
+    mut rest: Option<&mut Vec<String>>, loader: Option<(&str, &mut ConfigLoader)>) 
+    -> Result<ParseSummary<'def>, ParseError<'def>>
+  where T: Borrow<str> 
 { 
     let program = program.into();
-    let help = Rc::new(Help::new(program.clone(), &definitions));
-    let mut defs = parse_definitions(definitions)?;
+    let help = Rc::new(config.help(program.clone(), &definitions));
+    let names = definitions.iter().map(|def| def.name.clone()).collect::<Vec<_>>();
+    let mut warnings = Vec::new();
+    let defs = parse_definitions(definitions, config, &mut warnings);
+    for warning in &warnings {
+        (config.warnings)(warning);
+    }
+    let mut defs = defs?;
     
     //println!("Defs: {:?}", defs);
-    let mut args = args.iter().map(|e| e.borrow());
+    if let Some(max) = config.max_args {
+        if args.len() > max {
+            return ParseError::parse(ParseFailure::new(FailureKind::TooManyArguments, 
+                format!("Too many arguments: {} were given, but at most {} are allowed", args.len(), max)), 
+                help);
+        }
+    }
+    let mut args = args.iter().map(|e| e.borrow()).peekable();
+    
+    // The config option gives defaults for the other options (see `parse_two_pass`).
+    if let Some((option, loader)) = loader {
+        let all = args.clone().collect::<Vec<_>>();
+        if let Some(path) = defs.find_value(&all, option, help.clone(), config) {
+            match loader(path) {
+                Ok(defaults) => config.defaults.extend(defaults),
+                Err(msg) => {
+                    return ParseError::parse(ParseFailure::new(FailureKind::BadValue, msg)
+                        .option(format!("{}{}", config.prefixes.long, option)).value(path), help);
+                }
+            }
+        }
+    }
     
     // value-type definitions that have been given and should not be overridden
     let mut given_values = HashSet::new();
     
+    // Eager options are read first, unless the arguments may not all be ours.
+    if ! defs.eager.is_empty() && rest.is_none() {
+        let all = args.clone().collect::<Vec<_>>();
+        if let Some(interrupt) = defs.read_eager(&all, &mut given_values, help.clone(), config)? {
+            return ParseError::interrupt(interrupt, &config.prefixes);
+        }
+    }
+    
+    // The names of the given options, with the arguments that gave them.
+    let mut spellings = Vec::new();
+    
+    // Whether a '--' has been passed, so that options are no longer read.
+    let mut options_ended = false;
+    
+    // The number of positional and trail values given so far.
+    let mut positional_count = 0;
+    
     while let Some(arg) = args.next() {
+        if let Some(ref mut rest) = rest {
+            if ! defs.can_read(arg, options_ended, config) {
+                rest.push(arg.to_string());
+                rest.extend(args.by_ref().map(|arg| arg.to_string()));
+                break;
+            }
+        }
+        
+        // End of options
+        if arg == config.prefixes.long && ! options_ended {
+            options_ended = true;
+        
+        // Flag toggle
+        } else if config.plus_toggles && arg.starts_with("+") && arg != "+" && ! options_ended {
+            defs.check_option_order(arg, help.clone(), config)?;
+            let name = defs.read_toggle(arg, &mut given_values, help.clone())?;
+            if config.record_spellings {
+                spellings.push((name, arg.to_string()));
+            }
+        
+        // Unknown long option, kept for the trail
+        } else if ! options_ended && defs.passes_to_trail(arg, config) {
+            defs.add_to_trail(arg, help.clone())?;
+        
         // Option / interrupt
-        if arg.starts_with("-") {
-            if let Some(interrupt) = defs.read_option(arg, &mut args, &mut given_values, help.clone())? {
-                return ParseError::interrupt(interrupt);
+        } else if config.prefixes.split(arg).is_some() && ! options_ended && ! defs.is_value(arg, config) {
+            let name = if let Some(name) = defs.eager_name(arg, help.clone()) {
+                name
+            } else {
+                defs.check_option_order(arg, help.clone(), config)?;
+                let (name, interrupt) = defs.read_option(arg, &mut args, &mut given_values, help.clone(), config)?;
+                if let Some(exit_code) = interrupt {
+                    return ParseError::interrupt((name, exit_code), &config.prefixes);
+                }
+                name
+            };
+            if config.empty_value_warnings && config.prefixes.split_attached(arg).1 == Some("") {
+                if let Some(TargetRef::Setting { .. }) = defs.options.get(&name) {
+                    match args.peek() {
+                        Some(&next) if ! next.is_empty() && config.prefixes.split(next).is_none() => {
+                            (config.warnings)(&format!("'{}' was given an empty value, and '{}' follows it. Did you mean '{}{}'?", 
+                                arg, next, arg, next.trim()));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            if config.record_spellings {
+                spellings.push((name, arg.to_string()));
             }
         
         // Positional
         } else if ! defs.positional.is_empty() {
-            let (_name, target) = defs.positional.pop_front().unwrap();
-            match target.parse(arg) {
-                Ok(()) => {},
-                Err(msg) => return ParseError::parse(msg, help),
-            } // MAYBE: chain err
+            positional_count += 1;
+            check_positional_count(config, positional_count, arg, help.clone())?;
+            let (name, target) = defs.positional.pop_front().unwrap();
+            let arg = trim_if(&defs.trimmed, &name, arg);
+            let is_command = defs.command.as_ref().is_some_and(|command| command.0 == name);
+            defs.effective_values.push(arg.to_string());
+            defs.context.insert(name.as_ref(), arg);
+            if arg.is_empty() && defs.non_empty.contains(&name) {
+                return ParseError::parse(ParseFailure::new(FailureKind::BadValue, 
+                    format!("Argument '{}' must not be empty", name)).option(name.as_ref()).value(arg), help);
+            }
+            if let Some(Err(msg)) = defs.signs.get(&name).map(|sign| sign.check(&name, arg)) {
+                return ParseError::parse(ParseFailure::new(FailureKind::BadValue, msg)
+                    .option(name.as_ref()).value(arg), help);
+            }
+            if let Some(ref mut deferred) = defs.deferred {
+                deferred.push(Deferred::Positional(name, target, arg.to_string()));
+            } else {
+                match target.parse(arg) {
+                    Ok(()) => {},
+                    Err(msg) => return ParseError::parse(ParseFailure::new(FailureKind::BadValue, msg)
+                        .option(name.as_ref()).value(arg), help),
+                } // MAYBE: chain err
+            }
+            // Everything after the command is passed on as it is.
+            if is_command {
+                let values = args.by_ref().map(|arg| arg.to_string()).collect::<Vec<_>>();
+                if let Some((_, ref mut rest)) = defs.command {
+                    if ! defs.dry_run {
+                        rest.extend(values.iter().cloned());
+                    }
+                }
+                defs.effective_values.extend(values);
+            }
         
         // Subcommand
         } else if ! defs.subcommands.is_empty() {
-            if let Some(handler) = defs.subcommands.get_mut(arg) {
-                let rest = args.collect::<Vec<_>>();
+            if defs.subcommands.contains_key(arg) {
+                if let Some(option) = defs.without_subcommand.iter().find(|name| given_values.contains(*name)) {
+                    let option = format!("{}{}", config.prefixes.long, option);
+                    return ParseError::parse(ParseFailure::new(FailureKind::ConflictingOptions, 
+                        format!("{} cannot be combined with subcommand '{}'", option, arg))
+                        .option(option).value(arg), help);
+                }
+                for (option, scopes) in &defs.scopes {
+                    if given_values.contains(option) && ! scopes.iter().any(|scope| scope == arg) {
+                        let option = format!("{}{}", config.prefixes.long, option);
+                        let scopes = scopes.iter().map(|scope| format!("'{}'", scope)).collect::<Vec<_>>();
+                        let subcommands = if scopes.len() == 1 { "the subcommand" } else { "the subcommands" };
+                        return ParseError::parse(ParseFailure::new(FailureKind::ConflictingOptions, 
+                            format!("Option '{}' can only be used with {} {}, not '{}'", 
+                                option, subcommands, scopes.join(", "), arg))
+                            .option(option).value(arg), help);
+                    }
+                }
+                defs.apply_implications(&mut given_values);
+                defs.check_required(&given_values, &config.defaults, help.clone())?;
+                defs.check_relations(&given_values, help.clone())?;
+                defs.apply_defaults(config, &given_values, help.clone())?;
+                defs.apply_env_flags(config, &given_values, help.clone())?;
+                defs.apply_computed_defaults(config, &given_values, help.clone())?;
+                defs.apply_templates(help.clone())?;
+                defs.parse_deferred(help)?;
+                let flags = defs.flag_values(&given_values);
+                if defs.dry_run {
+                    return Ok(ParseSummary::new(None, given_values, flags, spellings, names, Vec::new()));
+                }
+                let handler = defs.subcommands.get_mut(arg).unwrap();
+                let mut rest = args.collect::<Vec<_>>();
+                // Options have also ended for the subcommand.
+                if options_ended {
+                    rest.insert(0, config.prefixes.long.as_str());
+                }
                 // Allow 'empty' super-program.
-                let subprogram = if program != "" {
+                let subprogram = if ! program.is_empty() {
                     format!("{} {}", program, arg)
                 } else {
                     arg.to_string()
                 };
-                return handler(subprogram, &rest);
+                let exit_code = handler(subprogram, &rest)?;
+                let mut command = vec![program];
+                command.append(&mut defs.effective_options);
+                command.push(arg.to_string());
+                command.extend(rest.iter().map(|arg| arg.to_string()));
+                return config.succeed(ParseSummary::new(exit_code, given_values, flags, spellings, names, command));
             } else {
-                return ParseError::parse(format!("Unknown subcommand: '{}'", arg), help);
+                let expected = help.subcommands.iter().map(|(name, _)| name.as_ref())
+                    .collect::<Vec<_>>().join(", ");
+                return ParseError::parse(ParseFailure::new(FailureKind::UnknownSubcommand, 
+                    format!("Unknown subcommand: '{}' (expected a subcommand: {})", arg, expected))
+                    .value(arg), help);
             }
         
         // Trail
         } else {
-            if let Some((_, ref mut satisfied, ref mut target)) = defs.trail {
-                match target.parse_and_add(arg) {
-                    Ok(()) => {},
-                    Err(msg) => return ParseError::parse(msg, help),
-                }; // TODO: chain err
-                *satisfied = true;
+            if let Some(delimiter) = defs.trail.as_ref().map(|trail| trail.3) {
+                positional_count += 1;
+                check_positional_count(config, positional_count, arg, help.clone())?;
+                for arg in split_values(vec![arg], delimiter) {
+                    defs.add_to_trail(arg, help.clone())?;
+                }
             } else {
-                return ParseError::parse(format!("Unexpected argument '{}'", arg), help);
+                let context = if help.positional.is_empty() {
+                    String::from("no positional arguments are expected")
+                } else {
+                    let names = help.positional.iter().map(|(name, _)| name.as_ref())
+                        .collect::<Vec<_>>().join(", ");
+                    format!("no arguments are expected after {}", names)
+                };
+                return ParseError::parse(ParseFailure::new(FailureKind::UnexpectedArgument, 
+                    format!("Unexpected argument '{}' ({})", arg, context)).value(arg), help);
             }            
         }
     }
     
+    defs.apply_implications(&mut given_values);
+    defs.check_required(&given_values, &config.defaults, help.clone())?;
+    defs.check_relations(&given_values, help.clone())?;
+    defs.apply_defaults(config, &given_values, help.clone())?;
+    defs.apply_env_flags(config, &given_values, help.clone())?;
+    defs.apply_positional_defaults(help.clone())?;
+    defs.apply_computed_defaults(config, &given_values, help.clone())?;
+    defs.apply_templates(help.clone())?;
+    let flags = defs.flag_values(&given_values);
+    
     if let Some((name, _)) = defs.positional.pop_front() {
-        return ParseError::parse(format!("Missing positional argument '{}'", name), help);
+        return ParseError::parse(ParseFailure::new(FailureKind::MissingArgument, 
+            format!("Missing positional argument '{}'", name)).option(name.as_ref()), help);
     }
     
-    if let Some((name, satisfied, _)) = defs.trail {
+    if let Some((ref name, satisfied, _, _)) = defs.trail {
         if ! satisfied {
-            return ParseError::parse(format!("Expected at least one trailing argument for '{}'", name), help);
+            return ParseError::parse(ParseFailure::new(FailureKind::MissingArgument, 
+                format!("Expected at least one trailing argument for '{}'", name))
+                .option(name.as_ref()), help);
+        }
+        if let Some((min, _)) = defs.trail_bounds {
+            if defs.trail_count < min {
+                return ParseError::parse(ParseFailure::new(FailureKind::MissingArgument, 
+                    format!("Expected at least {} trailing arguments for '{}', but got {}", 
+                        min, name, defs.trail_count))
+                    .option(name.as_ref()), help);
+            }
         }
     }
     
     if ! defs.subcommands.is_empty() {
-        return ParseError::parse(format!("No subcommand specified"), help);
+        return ParseError::parse(ParseFailure::new(FailureKind::MissingSubcommand, 
+            "No subcommand specified"), help);
     }
     
-    Ok(None)
+    defs.parse_deferred(help)?;
+    
+    let mut command = vec![program];
+    command.append(&mut defs.effective_options);
+    if ! defs.effective_values.is_empty() {
+        command.push(config.prefixes.long.clone());
+        command.append(&mut defs.effective_values);
+    }
+    config.succeed(ParseSummary::new(None, given_values, flags, spellings, names, command))
 }
 
 /// Parses the given arguments and updates the defined variables with them.
@@ -307,19 +2162,276 @@ pub fn parse_plain<'def, 'tar, T, P: Into<String>>(program: P, args: &[T], defin
 /// - Invalid argument definitions (logic error): Panic.
 /// - Parse failed: Print usage and prevent the error from propagating.
 /// - Interrupt or sub parse failed: Just passed along.
+///
+/// Use `parse_with` and `ParseConfig::reporter` to report failures some 
+/// other way than printing them.
 pub fn parse<'def, 'tar, T, P: Into<String>>(program: P, args: &[T], definitions: Vec<ArgDef<'def, 'tar>>) 
     -> Result<Option<i32>, ParseError<'def>>
   where T: Borrow<str> 
 { 
-    match parse_plain(program, args, definitions) {
+    parse_with(program, args, definitions, ParseConfig::new())
+        .map(|summary| summary.exit_code)
+}
+
+/// The result of a dry run parse (see `dry_run_parse`).
+#[derive(Debug, Clone)]
+pub enum DryRunOutcome<'def> {
+    /// The arguments would be parsed successfully.
+    Success,
+    /// The arguments would trigger the interrupt with the given name and 
+    /// exit code.
+    Interrupted(Cow<'def, str>, i32),
+    /// The parse would fail.
+    Failed(ParseFailure),
+    /// The given argument definitions aren't valid.
+    InvalidDefinitions(String),
+}
+
+/// Checks what parsing the arguments would do, without running interrupt 
+/// callbacks or subcommand handlers, and without changing any target.
+///
+/// This is useful for checking generated command lines, like in tests. The
+/// first interrupt given is reported, even if its callback would continue 
+/// the parse. Values are not parsed, since parsing them is what writes them
+/// to their targets, so a bad value (like `--port x` for a number) is not
+/// found, but missing, unknown and conflicting arguments are. The arguments
+/// of a subcommand are not checked either.
+///
+/// # Example
+/// ```
+/// # use argonaut::{ArgDef, dry_run_parse, DryRunOutcome};
+/// let mut verbose = false;
+/// let mut file = String::new();
+/// let outcome = dry_run_parse("prog", &["-h", "-v"], vec![
+///     ArgDef::flag("verbose", &mut verbose).short("v"),
+///     ArgDef::positional("file", &mut file),
+///     ArgDef::interrupt("help", |_| panic!("Not run in a dry run")).short("h"),
+/// ]);
+/// match outcome {
+///     DryRunOutcome::Interrupted(name, _) => assert_eq!("help", name),
+///     other => panic!("Unexpected outcome: {:?}", other),
+/// }
+/// assert!(! verbose);
+/// ```
+pub fn dry_run_parse<'def, 'tar, T, P: Into<String>>(program: P, args: &[T], definitions: Vec<ArgDef<'def, 'tar>>) 
+    -> DryRunOutcome<'def>
+  where T: Borrow<str> 
+{ 
+    let mut config = ParseConfig::new();
+    config.dry_run = true;
+    match parse_configured(program, args, definitions, &mut config, None, None) {
+        Ok(_) => DryRunOutcome::Success,
+        Err(ParseError::Interrupted(name, exit_code, _)) => DryRunOutcome::Interrupted(name, exit_code),
+        Err(ParseError::ParseFailed(failure, _)) => DryRunOutcome::Failed(failure),
+        Err(ParseError::InvalidDefinitions(msg)) => DryRunOutcome::InvalidDefinitions(msg),
+        Err(ParseError::SubParseFailed) => unreachable!("Subcommands are not run in a dry run"),
+    }
+}
+
+/// Like `parse`, but handles the errors by exiting the process, so a 
+/// successful parse is all that is returned.
+///
+/// A failed parse prints the usage (like `parse`) and exits with code 1.
+/// An interrupt like `--help` has already done its job, so the process exits
+/// with the exit code of the interrupt (0 unless `ArgDef::exit_code` says
+/// otherwise). The exit code of a subcommand is left in the summary.
+///
+/// This replaces the `match` on the result in `main`. The process exits 
+/// without running the destructors of the caller, so tests (and programs that
+/// need to clean up) should use `parse` or `parse_with` instead.
+///
+/// # Example
+/// ```
+/// # use argonaut::{ArgDef, parse_or_exit};
+/// let mut verbose = false;
+/// let summary = parse_or_exit("prog", &["-v"], vec![
+///     ArgDef::flag("verbose", &mut verbose).short("v"),
+///     ArgDef::default_help("A program.").short("h"),
+/// ]);
+/// assert!(summary.was_given("verbose"));
+/// assert!(verbose);
+/// ```
+pub fn parse_or_exit<'def, 'tar, T, P: Into<String>>(program: P, args: &[T], definitions: Vec<ArgDef<'def, 'tar>>) 
+    -> ParseSummary<'def>
+  where T: Borrow<str> 
+{ 
+    match parse_with(program, args, definitions, ParseConfig::new()) {
+        Ok(summary) => summary,
+        Err(error) => process::exit(error.exit_code()),
+    }
+}
+
+/// Parses the arguments in two passes, to let a configuration file provide
+/// defaults for the options.
+/// 
+/// The first pass only looks for `--<config_option> FILE` or 
+/// `--<config_option>=FILE` (before any `--`). It reads the options like the
+/// full pass does, so it skips the values of the other options 
+/// (`--message --config` sets the message), and matches the config option
+/// by its short identifier, or by an abbreviation or another case when the
+/// config allows it.
+/// If it is given, the loader is called with the path, and the values it 
+/// returns are used as defaults for options that aren't given in the second,
+/// full, pass. Errors from the loader are reported as parse failures.
+///
+/// The config option should itself be defined as a setting, so that the 
+/// second pass accepts it. Otherwise this behaves like `parse_with`.
+///
+/// The value doesn't have to be a path: a loader can also look up a named
+/// profile (`--profile fast`), which is how a parse is re-run with the
+/// defaults an option selects, since the definitions are consumed by it.
+///
+/// # Example
+/// ```
+/// # use argonaut::{ArgDef, ParseConfig, parse_two_pass};
+/// # use std::collections::HashMap;
+/// let load = |path: &str| {
+///     assert_eq!("prod.conf", path);
+///     let mut defaults = HashMap::new();
+///     defaults.insert("port".to_string(), "443".to_string());
+///     Ok(defaults)
+/// };
+/// let (mut config, mut message, mut port) = (None::<String>, None::<String>, None::<u16>);
+/// parse_two_pass("prog", &["--message", "--config", "--conf", "prod.conf"], vec![
+///     ArgDef::setting("config", &mut config),
+///     ArgDef::setting("message", &mut message),
+///     ArgDef::setting("port", &mut port),
+/// ], "config", load, ParseConfig::new().abbreviations(true)).unwrap();
+/// assert_eq!(Some("--config".to_string()), message);
+/// assert_eq!(Some(443), port);
+///
+/// // The message only looks like the config option, so nothing is loaded.
+/// let (mut config, mut message) = (None::<String>, None::<String>);
+/// parse_two_pass("prog", &["--message", "--config"], vec![
+///     ArgDef::setting("config", &mut config),
+///     ArgDef::setting("message", &mut message),
+/// ], "config", |_| Err("not loaded".to_string()), ParseConfig::new()).unwrap();
+/// assert_eq!(Some("--config".to_string()), message);
+/// ```
+pub fn parse_two_pass<'def, 'tar, T, P, F>(program: P, args: &[T], 
+    definitions: Vec<ArgDef<'def, 'tar>>, config_option: &str, loader: F, 
+    config: ParseConfig<'def>) 
+    -> Result<ParseSummary<'def>, ParseError<'def>>
+  where T: Borrow<str>,
+        P: Into<String>,
+        F: FnOnce(&str) -> Result<HashMap<String, String>, String>
+{ 
+    let mut loader = Some(loader);
+    let mut load = |path: &str| match loader.take() {
+        Some(loader) => loader(path),
+        None => Ok(HashMap::new()),
+    };
+    parse_reported(program, args, definitions, config, Some((config_option, &mut load)))
+}
+
+/// Like `parse`, but uses the given configuration, and returns a summary of 
+/// the parse when it succeeds.
+pub fn parse_with<'def, 'tar, T, P: Into<String>>(program: P, args: &[T], 
+    definitions: Vec<ArgDef<'def, 'tar>>, config: ParseConfig<'def>) 
+    -> Result<ParseSummary<'def>, ParseError<'def>>
+  where T: Borrow<str> 
+{ 
+    parse_reported(program, args, definitions, config, None)
+}
+
+/// Parses with the config, and reports the failures with its reporter.
+fn parse_reported<'def, 'tar, T, P: Into<String>>(program: P, args: &[T], 
+    definitions: Vec<ArgDef<'def, 'tar>>, mut config: ParseConfig<'def>, 
+    loader: Option<(&str, &mut ConfigLoader)>) 
+    -> Result<ParseSummary<'def>, ParseError<'def>>
+  where T: Borrow<str> 
+{ 
+    match parse_configured(program, args, definitions, &mut config, None, loader) {
         Err(ParseError::InvalidDefinitions(msg)) => {
             panic!("Invalid definitions: {}", msg);
         }
-        Err(ParseError::ParseFailed(msg, help)) => {
-            println!("Parse failed: {}", msg);
-            help.print_usage();
+        Err(ParseError::ParseFailed(failure, help)) => {
+            config.reporter.report(&failure, &help);
             Err(ParseError::SubParseFailed)
         }
         other => other,
     }
 }
+
+/// Like `parse`, but takes the arguments as OS strings, like those from 
+/// `std::env::args_os`.
+///
+/// Every argument must be valid UTF-8, wherever it is: option names and 
+/// values, positionals, subcommands and trails (even after `--`) are all 
+/// read as strings. An argument that isn't fails the parse before any of 
+/// them is read, with an `InvalidUtf8` failure like 
+/// "Argument 2 is not valid UTF-8: 'b\u{FFFD}d'", where arguments are counted
+/// from 1 (leaving out the program), and the value is shown with the bad 
+/// bytes replaced. Nothing is ever converted lossily into a target.
+///
+/// # Example
+/// ```
+/// # use argonaut::{ArgDef, parse_os};
+/// # use std::ffi::OsString;
+/// let mut name = None::<String>;
+/// let args = vec![OsString::from("--name"), OsString::from("Ferris")];
+/// parse_os("prog", &args, vec![
+///     ArgDef::setting("name", &mut name),
+/// ]).unwrap();
+/// assert_eq!(Some("Ferris".to_string()), name);
+/// ```
+pub fn parse_os<'def, 'tar, T, P: Into<String>>(program: P, args: &[T], definitions: Vec<ArgDef<'def, 'tar>>) 
+    -> Result<Option<i32>, ParseError<'def>>
+  where T: AsRef<OsStr> 
+{ 
+    parse_os_with(program, args, definitions, ParseConfig::new())
+        .map(|summary| summary.exit_code)
+}
+
+/// Like `parse_os`, but uses the given configuration, and returns a summary
+/// of the parse when it succeeds (like `parse_with`).
+///
+/// An argument that isn't valid UTF-8 is reported like other failures.
+///
+/// # Example
+/// ```
+/// # use argonaut::{ArgDef, parse_os_with, ParseConfig, ParseFailure, Help, ErrorReporter};
+/// # #[cfg(unix)] {
+/// use std::ffi::OsString;
+/// use std::os::unix::ffi::OsStringExt;
+///
+/// struct Check;
+/// impl ErrorReporter for Check {
+///     fn report(&mut self, failure: &ParseFailure, _help: &Help) {
+///         assert_eq!("invalid_utf8", failure.kind.code());
+///         assert_eq!("Argument 2 is not valid UTF-8: 'b\u{FFFD}d'", failure.message);
+///     }
+/// }
+///
+/// let mut files: Vec<String> = Vec::new();
+/// let args = vec![OsString::from("a.txt"), OsString::from_vec(vec![b'b', 0xff, b'd'])];
+/// let result = parse_os_with("prog", &args, vec![
+///     ArgDef::trail("files", true, &mut files),
+/// ], ParseConfig::new().reporter(Check));
+/// assert!(result.is_err());
+/// assert!(files.is_empty());
+/// # }
+/// ```
+pub fn parse_os_with<'def, 'tar, T, P: Into<String>>(program: P, args: &[T], 
+    definitions: Vec<ArgDef<'def, 'tar>>, mut config: ParseConfig<'def>) 
+    -> Result<ParseSummary<'def>, ParseError<'def>>
+  where T: AsRef<OsStr> 
+{ 
+    let program = program.into();
+    let mut strings = Vec::with_capacity(args.len());
+    for (index, arg) in args.iter().enumerate() {
+        match arg.as_ref().to_str() {
+            Some(arg) => strings.push(arg),
+            None => {
+                let lossy = arg.as_ref().to_string_lossy();
+                let failure = ParseFailure::new(FailureKind::InvalidUtf8, 
+                    format!("Argument {} is not valid UTF-8: '{}'", index + 1, lossy))
+                    .value(lossy.into_owned());
+                let help = config.help(program, &definitions);
+                config.reporter.report(&failure, &help);
+                return Err(ParseError::SubParseFailed);
+            }
+        }
+    }
+    parse_with(program, &strings, definitions, config)
+}
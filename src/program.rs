@@ -0,0 +1,37 @@
+use std::env;
+use std::path::Path;
+
+/// The name of the program, as shown in usage and help messages.
+///
+/// Anything that takes a program name as `Into<String>` also takes this, so
+/// `parse(Program::Auto, ..)` works like `parse("name", ..)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Program {
+    /// Use the file stem of the running binary (`std::env::args().next()`),
+    /// so that messages stay correct when the binary is renamed or linked.
+    /// This is empty if the binary's path isn't known.
+    Auto,
+    /// Use the given name.
+    Named(String),
+}
+
+impl Program {
+    /// Returns the name this stands for.
+    pub fn name(&self) -> String {
+        match *self {
+            Program::Auto => {
+                env::args().next()
+                    .and_then(|path| Path::new(&path).file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned()))
+                    .unwrap_or_default()
+            }
+            Program::Named(ref name) => name.clone(),
+        }
+    }
+}
+
+impl From<Program> for String {
+    fn from(program: Program) -> String {
+        program.name()
+    }
+}
@@ -0,0 +1,72 @@
+use help::Help;
+use parse::ParseFailure;
+
+/// Reports failed parses to the user.
+///
+/// Implement this to send parse errors somewhere other than stdout, like a 
+/// logger or a dialog.
+pub trait ErrorReporter {
+    /// Reports that the parse failed.
+    fn report(&mut self, failure: &ParseFailure, help: &Help);
+}
+
+/// The default reporter, which prints the error after the header of the help
+/// (like `Parse failed: `), and a usage message, or a hint to `--help` when
+/// the help asks for one (see `ParseConfig::help_hint`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrintReporter;
+
+impl ErrorReporter for PrintReporter {
+    fn report(&mut self, failure: &ParseFailure, help: &Help) {
+        println!("{}{}", help.error_header, failure);
+        match help.hint_message() {
+            Some(ref hint) if help.help_hint => println!("{}", hint),
+            _ => help.print_usage(),
+        }
+    }
+}
+
+/// A reporter for machine consumers, which prints the error as a single 
+/// line of JSON (see `ParseFailure::to_json`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonReporter;
+
+impl ErrorReporter for JsonReporter {
+    fn report(&mut self, failure: &ParseFailure, _help: &Help) {
+        println!("{}", failure.to_json());
+    }
+}
+
+/// Writes a JSON object with the given string fields, leaving out the ones
+/// that are `None`.
+pub(crate) fn json_object(fields: &[(&str, Option<&str>)]) -> String {
+    let mut s = String::from("{");
+    for &(key, value) in fields {
+        if let Some(value) = value {
+            if s.len() > 1 {
+                s.push(',');
+            }
+            push_json_string(&mut s, key);
+            s.push(':');
+            push_json_string(&mut s, value);
+        }
+    }
+    s.push('}');
+    s
+}
+
+fn push_json_string(s: &mut String, text: &str) {
+    s.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if (c as u32) < 0x20 => s.push_str(&format!("\\u{:04x}", c as u32)),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+}
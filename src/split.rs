@@ -0,0 +1,90 @@
+/// Splits a command line into arguments, like a POSIX shell would.
+///
+/// Arguments are separated by whitespace. Single quotes keep everything
+/// inside them literally, double quotes allow `\"`, `\\`, `\$` and `` \` ``
+/// escapes (and a backslash before a newline removes both), a backslash 
+/// outside quotes escapes the next character. `''` and `""` give empty 
+/// arguments. Nothing is expanded, so `$` and `` ` `` are otherwise kept.
+///
+/// Unterminated quotes and trailing backslashes are errors.
+///
+/// # Example
+/// ```
+/// # use argonaut::split_line;
+/// assert_eq!(vec!["a b", "$HOME", "`cmd`", "\\n", ""], 
+///            split_line(r#"'a b' "\$HOME" "\`cmd\`" "\n" """#).unwrap());
+/// ```
+pub fn split_line(line: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut in_arg = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => return Err(format!("Unterminated single quote in '{}'", line)),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ '"') | Some(c @ '\\') | Some(c @ '$') | Some(c @ '`') => arg.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                arg.push('\\');
+                                arg.push(c);
+                            }
+                            None => return Err(format!("Unterminated double quote in '{}'", line)),
+                        },
+                        Some(c) => arg.push(c),
+                        None => return Err(format!("Unterminated double quote in '{}'", line)),
+                    }
+                }
+            }
+            '\\' => {
+                in_arg = true;
+                match chars.next() {
+                    Some(c) => arg.push(c),
+                    None => return Err(format!("Trailing backslash in '{}'", line)),
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(arg);
+                    arg = String::new();
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                arg.push(c);
+            }
+        }
+    }
+    if in_arg {
+        args.push(arg);
+    }
+    Ok(args)
+}
+
+/// Quotes an argument for a POSIX shell, so that `split_line` gives it back.
+///
+/// Arguments with only safe characters are left as they are, others are put
+/// in single quotes.
+pub(crate) fn quote_arg(arg: &str) -> String {
+    let safe = |c: char| c.is_alphanumeric() || "-_./=:,+@%".contains(c);
+    if ! arg.is_empty() && arg.chars().all(safe) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
@@ -0,0 +1,156 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use argdef::OptHandle;
+use split::quote_arg;
+
+/// Something that identifies a defined argument: its name or a handle to it.
+pub trait OptKey {
+    /// Finds the name of the argument among the given definition names.
+    fn resolve<'a>(&'a self, names: &'a [Cow<str>]) -> Option<&'a str>;
+}
+
+impl OptKey for &str {
+    fn resolve<'a>(&'a self, _names: &'a [Cow<str>]) -> Option<&'a str> {
+        Some(*self)
+    }
+}
+
+impl OptKey for String {
+    fn resolve<'a>(&'a self, _names: &'a [Cow<str>]) -> Option<&'a str> {
+        Some(self)
+    }
+}
+
+impl OptKey for &String {
+    fn resolve<'a>(&'a self, _names: &'a [Cow<str>]) -> Option<&'a str> {
+        Some(self)
+    }
+}
+
+impl OptKey for OptHandle {
+    fn resolve<'a>(&'a self, names: &'a [Cow<str>]) -> Option<&'a str> {
+        names.get(self.0).map(|name| name.as_ref())
+    }
+}
+
+/// Whether a flag was given in the arguments, and what it was set to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlagState {
+    /// The flag was given and set to the value.
+    Set(bool),
+    /// The flag was not given, so its target kept its previous value.
+    Unset,
+}
+
+/// A description of a successful parse.
+#[derive(Debug)]
+pub struct ParseSummary<'def> {
+    /// The value returned by the subcommand handler, if a subcommand was run.
+    pub exit_code: Option<i32>,
+    /// The names of the options that were given in the arguments.
+    pub(crate) given: HashSet<Cow<'def, str>>,
+    /// The values of the flags that were given.
+    pub(crate) flags: HashMap<Cow<'def, str>, bool>,
+    /// The names of the given options, with the arguments that gave them.
+    pub(crate) spellings: Vec<(Cow<'def, str>, String)>,
+    /// The names of all definitions, in the order they were defined.
+    pub(crate) names: Vec<Cow<'def, str>>,
+    /// The program and the canonical arguments that reproduce the parse.
+    pub(crate) command: Vec<String>,
+}
+
+impl<'def> ParseSummary<'def> {
+    pub(crate) fn new(exit_code: Option<i32>, given: HashSet<Cow<'def, str>>, 
+            flags: HashMap<Cow<'def, str>, bool>, spellings: Vec<(Cow<'def, str>, String)>,
+            names: Vec<Cow<'def, str>>, command: Vec<String>) -> ParseSummary<'def> {
+        ParseSummary { exit_code, given, flags, spellings, names, command }
+    }
+
+    /// Returns whether the given option was passed.
+    ///
+    /// The option can be referred to by its (long) name or by the handle
+    /// returned from `ArgDef::register`.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, ParseConfig, parse_with};
+    /// let mut verbose = false;
+    /// let summary = parse_with("prog", &["-v"], vec![
+    ///     ArgDef::flag("verbose", &mut verbose).short("v"),
+    /// ], ParseConfig::new()).unwrap();
+    /// let name = String::from("verbose");
+    /// assert!(summary.was_given(&name));
+    /// assert!(summary.was_given(name));
+    /// assert!(! summary.was_given("quiet"));
+    /// ```
+    pub fn was_given<K: OptKey>(&self, option: K) -> bool {
+        match option.resolve(&self.names) {
+            Some(name) => self.given.contains(name),
+            None => false,
+        }
+    }
+
+    /// Returns the (long) names of the options that were given, in the order
+    /// they were defined.
+    ///
+    /// This is a snapshot of what the arguments decided, which is useful when
+    /// parsing again with other defaults (see `parse_two_pass`), to tell the
+    /// values that came from the arguments apart from the defaults.
+    pub fn given_options(&self) -> Vec<&str> {
+        self.names.iter().map(|name| name.as_ref())
+            .filter(|name| self.given.contains(*name))
+            .collect()
+    }
+    
+    /// Returns the (long) name of each option that was given, with the exact
+    /// argument that gave it, like `("verbose", "-v")` or 
+    /// `("output", "--output=x")`. A value given as its own argument is not
+    /// included, so `--output x` is recorded as `("output", "--output")`.
+    ///
+    /// The options are listed in the order they were given, once per time.
+    /// This is empty unless spellings are recorded in the config.
+    pub fn spellings(&self) -> &[(Cow<'def, str>, String)] {
+        &self.spellings
+    }
+
+    /// Returns whether the given flag was passed, and the value it was set to.
+    ///
+    /// This tells an explicitly disabled flag (like `-x` with `+` toggles) 
+    /// apart from one that was never mentioned, which is useful when merging
+    /// the arguments with other layers of configuration. Options that aren't 
+    /// flags are always `Unset`.
+    pub fn flag_state<K: OptKey>(&self, flag: K) -> FlagState {
+        match flag.resolve(&self.names).and_then(|name| self.flags.get(name)) {
+            Some(&value) => FlagState::Set(value),
+            None => FlagState::Unset,
+        }
+    }
+    
+    /// Returns a command line that reproduces this parse, like 
+    /// `prog --output=out.txt --verbose -- in.txt`, for logging.
+    ///
+    /// Options are written with their long names, in the order they were 
+    /// given, and the values of settings and collectors are attached with 
+    /// `=`. Options that got their values from the defaults of the config 
+    /// follow, so the command also works without them. Positional and trail
+    /// values come last, after `--`, and a subcommand is followed by its 
+    /// arguments as they were given. Arguments are quoted for a POSIX shell
+    /// where needed.
+    ///
+    /// # Example
+    /// ```
+    /// # use argonaut::{ArgDef, parse_with, ParseConfig};
+    /// let (mut verbose, mut output, mut input) = (false, None::<String>, String::new());
+    /// let summary = parse_with("prog", &["in put.txt", "-v", "-o", "out.txt"], vec![
+    ///     ArgDef::flag("verbose", &mut verbose).short("v"),
+    ///     ArgDef::setting("output", &mut output).short("o"),
+    ///     ArgDef::setting("level", &mut None::<u8>),
+    ///     ArgDef::positional("input", &mut input),
+    /// ], ParseConfig::new().defaults(vec![("level", "3")])).unwrap();
+    /// assert_eq!("prog --verbose --output=out.txt --level=3 -- 'in put.txt'", 
+    ///            summary.effective_command());
+    /// ```
+    pub fn effective_command(&self) -> String {
+        self.command.iter().map(|arg| quote_arg(arg)).collect::<Vec<_>>().join(" ")
+    }
+}